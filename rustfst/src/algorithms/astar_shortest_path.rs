@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use anyhow::Result;
+
+use crate::algorithms::queues::natural_less;
+use crate::algorithms::shortest_path::{trace_path, ShortestPathTrace};
+use crate::fst_traits::Fst;
+use crate::semirings::{Semiring, SemiringProperties};
+use crate::{StateId, Trs};
+
+/// State waiting to be expanded. `state` is `None` for the final candidates, whose final
+/// weight has been added and whose final state is `final_state`.
+#[derive(Debug, Clone)]
+struct AStarCandidate<W> {
+    /// Distance from the start state times the heuristic.
+    priority: W,
+    distance: W,
+    state: Option<StateId>,
+    final_state: StateId,
+    /// Insertion order, to break the ties.
+    id: usize,
+}
+
+impl<W: Semiring> PartialEq for AStarCandidate<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: Semiring> Eq for AStarCandidate<W> {}
+
+impl<W: Semiring> PartialOrd for AStarCandidate<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: Semiring> Ord for AStarCandidate<W> {
+    /// `BinaryHeap` is a max-heap : the greatest candidate is the one with the lowest
+    /// priority, then the oldest one.
+    fn cmp(&self, other: &Self) -> Ordering {
+        if natural_less(&self.priority, &other.priority).unwrap() {
+            Ordering::Greater
+        } else if natural_less(&other.priority, &self.priority).unwrap() {
+            Ordering::Less
+        } else {
+            other.id.cmp(&self.id)
+        }
+    }
+}
+
+/// Computes the shortest path of an FST with the A* algorithm, guided by a `heuristic`
+/// estimating the distance from each state to the final states. The weights must have the
+/// path property, e.g `TropicalWeight`.
+///
+/// The states are expanded by increasing distance from the start state times heuristic, so
+/// only the states that may be on a path shorter than the shortest path are visited. The FST
+/// doesn't need to be expanded : the trs are only computed for the visited states, which
+/// makes it possible to decode lazy FSTs like a `ComposeFst` without computing them. The
+/// path is the shortest one as long as the heuristic never overestimates the distance to the
+/// final states, e.g `W::one()` for weights without negative costs (Dijkstra's algorithm) or
+/// the reverse shortest distance of one of the operands of a composition. A heuristic of
+/// `W::zero()` marks a state from which no final state can be reached.
+///
+/// Returns `None` if the FST has no successful path.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::astar_shortest_path;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<TropicalWeight>::new();
+/// fst.add_states(4);
+/// fst.set_start(0)?;
+/// fst.set_final(3, TropicalWeight::one())?;
+/// fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+/// fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(2.0), 2))?;
+/// fst.add_tr(1, Tr::new(3, 3, TropicalWeight::new(5.0), 3))?;
+/// fst.add_tr(2, Tr::new(4, 4, TropicalWeight::new(1.0), 3))?;
+///
+/// // Exact distances to the final state.
+/// let heuristic = |s| TropicalWeight::new([3.0, 5.0, 1.0, 0.0][s]);
+/// let trace = astar_shortest_path(&fst, heuristic)?.unwrap();
+/// assert_eq!(trace.states, vec![0, 2, 3]);
+/// assert_eq!(trace.path.weight, TropicalWeight::new(3.0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn astar_shortest_path<W, F, H>(fst: &F, heuristic: H) -> Result<Option<ShortestPathTrace<W>>>
+where
+    W: Semiring,
+    F: Fst<W>,
+    H: Fn(StateId) -> W,
+{
+    if !W::properties().contains(SemiringProperties::PATH | SemiringProperties::RIGHT_SEMIRING) {
+        bail!(
+            "AStarShortestPath : Weight needs to have the path property and be right distributive"
+        )
+    }
+    let start = match fst.start() {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+
+    // The number of states isn't known, the vectors grow as the states are visited.
+    let mut distance: Vec<W> = vec![];
+    let mut parent: Vec<Option<(StateId, usize)>> = vec![];
+    let mut heap = BinaryHeap::new();
+    let mut num_candidates = 0;
+    let mut push = |heap: &mut BinaryHeap<AStarCandidate<W>>,
+                    priority: W,
+                    distance: W,
+                    state: Option<StateId>,
+                    final_state: StateId| {
+        heap.push(AStarCandidate {
+            priority,
+            distance,
+            state,
+            final_state,
+            id: num_candidates,
+        });
+        num_candidates += 1;
+    };
+
+    distance.resize(start + 1, W::zero());
+    parent.resize(start + 1, None);
+    distance[start] = W::one();
+    push(&mut heap, heuristic(start), W::one(), Some(start), start);
+
+    while let Some(candidate) = heap.pop() {
+        let state = match candidate.state {
+            Some(state) => state,
+            None => return Ok(Some(trace_path(fst, &parent, candidate.final_state)?)),
+        };
+        // A shorter path to this state has been found since it was pushed.
+        if candidate.distance != distance[state] {
+            continue;
+        }
+        if let Some(final_weight) = fst.final_weight(state)? {
+            let weight = candidate.distance.times(&final_weight)?;
+            push(&mut heap, weight.clone(), weight, None, state);
+        }
+        for (pos, tr) in fst.get_trs(state)?.trs().iter().enumerate() {
+            let nextstate = tr.nextstate;
+            if distance.len() <= nextstate {
+                distance.resize(nextstate + 1, W::zero());
+                parent.resize(nextstate + 1, None);
+            }
+            let weight = candidate.distance.times(&tr.weight)?;
+            if distance[nextstate] == distance[nextstate].plus(&weight)? {
+                continue;
+            }
+            let priority = weight.times(heuristic(nextstate))?;
+            if priority.is_zero() {
+                continue;
+            }
+            distance[nextstate] = weight.clone();
+            parent[nextstate] = Some((state, pos));
+            push(&mut heap, priority, weight, Some(nextstate), state);
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::sync::Arc;
+
+    use crate::algorithms::compose::ComposeFst;
+    use crate::algorithms::{shortest_distance, shortest_path_trace};
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{CoreFst, MutableFst};
+    use crate::semirings::{IntegerWeight, TropicalWeight};
+    use crate::Tr;
+
+    /// Chain of `n` states where state `i` goes to `i + 1` with a weight of 1 and to a
+    /// dead end with a weight of 0.5.
+    fn chain(n: usize) -> Result<VectorFst<TropicalWeight>> {
+        let mut fst = VectorFst::new();
+        fst.add_states(2 * n);
+        fst.set_start(0)?;
+        fst.set_final(n - 1, TropicalWeight::one())?;
+        for s in 0..n - 1 {
+            fst.add_tr(s, Tr::new(1, 1, TropicalWeight::new(0.5), n + s))?;
+            fst.add_tr(s, Tr::new(2, 2, TropicalWeight::new(1.0), s + 1))?;
+            fst.add_tr(n + s, Tr::new(3, 3, TropicalWeight::new(0.5), n + s + 1))?;
+        }
+        Ok(fst)
+    }
+
+    #[test]
+    fn test_astar_shortest_path_heuristic() -> Result<()> {
+        let fst = chain(10)?;
+        let expected = shortest_path_trace(&fst)?.unwrap();
+
+        // Without heuristic, the dead ends within the distance of the final state are
+        // expanded. The heuristic is called for each successor of an expanded state.
+        let visited = RefCell::new(vec![]);
+        let trace = astar_shortest_path(&fst, |s| {
+            visited.borrow_mut().push(s);
+            TropicalWeight::one()
+        })?;
+        assert_eq!(trace.as_ref(), Some(&expected));
+        assert!(visited.borrow().iter().any(|s| *s >= 11));
+
+        let distance = shortest_distance(&fst, true)?;
+        let visited = RefCell::new(vec![]);
+        let trace = astar_shortest_path(&fst, |s| {
+            visited.borrow_mut().push(s);
+            distance
+                .get(s)
+                .cloned()
+                .unwrap_or_else(TropicalWeight::zero)
+        })?;
+        assert_eq!(trace, Some(expected));
+        // Only the states of the path are expanded, the start state is also evaluated.
+        assert_eq!(visited.borrow().len(), 1 + 2 * 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_astar_shortest_path_lazy_compose() -> Result<()> {
+        let mut fst1 = VectorFst::<TropicalWeight>::new();
+        fst1.add_states(2);
+        fst1.set_start(0)?;
+        fst1.set_final(1, TropicalWeight::one())?;
+        fst1.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+        fst1.add_tr(0, Tr::new(2, 2, TropicalWeight::new(3.0), 1))?;
+        fst1.add_tr(1, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+        let mut fst2 = VectorFst::<TropicalWeight>::new();
+        fst2.add_states(3);
+        fst2.set_start(0)?;
+        fst2.set_final(2, TropicalWeight::one())?;
+        fst2.add_tr(0, Tr::new(1, 3, TropicalWeight::new(4.0), 1))?;
+        fst2.add_tr(0, Tr::new(2, 4, TropicalWeight::new(1.0), 1))?;
+        fst2.add_tr(1, Tr::new(1, 5, TropicalWeight::new(0.5), 2))?;
+
+        let compose_fst = ComposeFst::new_auto(Arc::new(fst1.clone()), Arc::new(fst2.clone()))?;
+        let trace = astar_shortest_path(&compose_fst, |_| TropicalWeight::one())?.unwrap();
+        assert_eq!(trace.path.ilabels, vec![2, 1]);
+        assert_eq!(trace.path.olabels, vec![4, 5]);
+        assert_eq!(trace.path.weight, TropicalWeight::new(5.5));
+        assert_eq!(trace.states[0], compose_fst.start().unwrap());
+        Ok(())
+    }
+
+    #[test]
+    fn test_astar_shortest_path_no_path() -> Result<()> {
+        let mut fst = chain(3)?;
+        assert_eq!(astar_shortest_path(&fst, |_| TropicalWeight::zero())?, None);
+        fst.delete_final_weight(2)?;
+        assert_eq!(astar_shortest_path(&fst, |_| TropicalWeight::one())?, None);
+        assert_eq!(
+            astar_shortest_path(&VectorFst::<TropicalWeight>::new(), |_| {
+                TropicalWeight::one()
+            })?,
+            None
+        );
+        assert!(
+            astar_shortest_path(&VectorFst::<IntegerWeight>::new(), |_| {
+                IntegerWeight::one()
+            })
+            .is_err()
+        );
+        Ok(())
+    }
+}
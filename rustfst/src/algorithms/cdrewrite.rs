@@ -0,0 +1,688 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use crate::algorithms::closure::{closure, ClosureType};
+use crate::algorithms::compose::compose;
+use crate::algorithms::concat::concat;
+use crate::algorithms::determinize::{determinize, DeterminizeType};
+use crate::algorithms::rm_epsilon::rm_epsilon;
+use crate::algorithms::tr_compares::{ilabel_compare, olabel_compare};
+use crate::algorithms::union::union;
+use crate::algorithms::{minimize, project, reverse, tr_sort, ProjectType};
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+use crate::semirings::{Semiring, TropicalWeight};
+use crate::trs::Trs;
+use crate::utils::acceptor;
+use crate::{Label, Tr, EPS_LABEL};
+
+/// Direction in which a context-dependent rewrite rule is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CdrewriteDirection {
+    /// The rule is applied from left to right : the left context is matched against
+    /// the rewritten output and the right context against the input.
+    LeftToRight,
+    /// The rule is applied from right to left : the right context is matched against
+    /// the rewritten output and the left context against the input.
+    RightToLeft,
+    /// Both contexts are matched against the input.
+    Simultaneous,
+}
+
+/// Whether a context-dependent rewrite rule must be applied or may be applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CdrewriteMode {
+    Obligatory,
+    Optional,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MarkerType {
+    /// Inserts the markers after each match of beta.
+    Mark,
+    /// Deletes the markers, only allowed after a match of beta.
+    Check,
+    /// Deletes the markers, only allowed when not after a match of beta.
+    CheckComplement,
+}
+
+/// Compiles the context-dependent rewrite rule `tau / lambda __ rho` into a transducer, following
+/// "An Efficient Compiler for Weighted Rewrite Rules", Mohri & Sproat, 1996.
+///
+/// The resulting transducer rewrites every match of the input side of `tau` that is preceded
+/// by `lambda` and followed by `rho` with the corresponding output of `tau` and leaves the rest of
+/// the string untouched. `lambda` and `rho` must be acceptors. The alphabet is made of all the
+/// labels of the acceptor `sigma_star`, which must contain all the labels of `tau`.
+/// The weights of `tau` are kept while `lambda`, `rho` and `sigma_star` are treated as unweighted.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use std::sync::Arc;
+/// # use rustfst::algorithms::{cdrewrite, CdrewriteDirection, CdrewriteMode, project, ProjectType};
+/// # use rustfst::algorithms::compose::compose;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::{acceptor, transducer};
+/// # fn main() -> Result<()> {
+/// // Rewrites 1 into 2 between 3 and 4.
+/// let tau: VectorFst<TropicalWeight> = transducer(&[1], &[2], TropicalWeight::one());
+/// let lambda: VectorFst<TropicalWeight> = acceptor(&[3], TropicalWeight::one());
+/// let rho: VectorFst<TropicalWeight> = acceptor(&[4], TropicalWeight::one());
+/// let mut sigma_star: VectorFst<TropicalWeight> = VectorFst::new();
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::Tr;
+/// let s = sigma_star.add_state();
+/// sigma_star.set_start(s)?;
+/// sigma_star.set_final(s, TropicalWeight::one())?;
+/// for label in 1..=4 {
+///     sigma_star.add_tr(s, Tr::new(label, label, TropicalWeight::one(), s))?;
+/// }
+///
+/// let rule = cdrewrite(
+///     &tau, &lambda, &rho, &sigma_star,
+///     CdrewriteDirection::LeftToRight, CdrewriteMode::Obligatory
+/// )?;
+///
+/// let input: VectorFst<TropicalWeight> = acceptor(&[3, 1, 4, 1, 4], TropicalWeight::one());
+/// let mut output: VectorFst<TropicalWeight> = compose(Arc::new(input), Arc::new(rule))?;
+/// project(&mut output, ProjectType::ProjectOutput);
+/// let paths: Vec<_> = output.paths_iter().map(|p| p.olabels).collect();
+/// assert_eq!(paths, vec![vec![3, 2, 4, 1, 4]]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn cdrewrite<W: Semiring, F: ExpandedFst<W>>(
+    tau: &F,
+    lambda: &F,
+    rho: &F,
+    sigma_star: &F,
+    direction: CdrewriteDirection,
+    mode: CdrewriteMode,
+) -> Result<VectorFst<W>> {
+    if !lambda.is_acceptor() || !rho.is_acceptor() || !sigma_star.is_acceptor() {
+        bail!("cdrewrite : lambda, rho and sigma_star must be acceptors");
+    }
+    let sigma = collect_labels(sigma_star);
+    for label in collect_labels(tau) {
+        if sigma.binary_search(&label).is_err() {
+            bail!(
+                "cdrewrite : label {} of tau is missing from sigma_star",
+                label
+            );
+        }
+    }
+
+    let tau: VectorFst<W> = copy_fst(tau, |w| w.clone());
+    let mut phi: VectorFst<TropicalWeight> = copy_fst(&tau, |_| TropicalWeight::one());
+    project(&mut phi, ProjectType::ProjectInput);
+    rm_epsilon(&mut phi)?;
+    let lambda: VectorFst<TropicalWeight> = copy_fst(lambda, |_| TropicalWeight::one());
+    let rho: VectorFst<TropicalWeight> = copy_fst(rho, |_| TropicalWeight::one());
+
+    // Markers are labels not used by any of the inputs.
+    let max_label = [&phi, &lambda, &rho]
+        .iter()
+        .flat_map(|fst| collect_labels(*fst))
+        .chain(sigma.iter().cloned())
+        .max()
+        .unwrap_or(EPS_LABEL);
+    let (m1, m2, m3) = (max_label + 1, max_label + 2, max_label + 3);
+    let optional = mode == CdrewriteMode::Optional;
+
+    let fsts = match direction {
+        CdrewriteDirection::LeftToRight | CdrewriteDirection::Simultaneous => {
+            let (lbrace1, lbrace2, rbrace) = (m1, m2, m3);
+            // Inserts `>` before each occurrence of rho.
+            let r = make_filter(&rho, &sigma, MarkerType::Mark, &[(EPS_LABEL, rbrace)], true)?;
+            // Inserts `<1` or `<2` before each occurrence of phi followed by `>`.
+            let mut phi_rbrace = ignore_inner_markers(&phi, &[rbrace])?;
+            let rbrace_fst: VectorFst<_> = acceptor(&[rbrace], TropicalWeight::one());
+            concat(&mut phi_rbrace, &rbrace_fst)?;
+            let f = make_filter(
+                &phi_rbrace,
+                &extend_sigma(&sigma, &[rbrace]),
+                MarkerType::Mark,
+                &[(EPS_LABEL, lbrace1), (EPS_LABEL, lbrace2)],
+                true,
+            )?;
+            if direction == CdrewriteDirection::LeftToRight {
+                // Rewrites phi between `<1` and `>`, removes `>`.
+                let mut initial_loops = vec![(lbrace2, lbrace2), (rbrace, EPS_LABEL)];
+                if optional {
+                    initial_loops.push((lbrace1, lbrace1));
+                }
+                let replace = make_replace(
+                    &tau,
+                    &sigma,
+                    (lbrace1, lbrace1),
+                    (rbrace, EPS_LABEL),
+                    &initial_loops,
+                    // Markers inside the rewritten region are no longer checked, only `<1`
+                    // is kept to avoid redundant paths.
+                    &[(lbrace1, EPS_LABEL), (rbrace, EPS_LABEL)],
+                )?;
+                // `<1` must be preceded by lambda, `<2` must not.
+                let l1 = make_filter(
+                    &ignore_markers(&lambda, &[lbrace2])?,
+                    &extend_sigma(&sigma, &[lbrace2]),
+                    MarkerType::Check,
+                    &[(lbrace1, EPS_LABEL)],
+                    false,
+                )?;
+                let l2 = make_filter(
+                    &lambda,
+                    &sigma,
+                    MarkerType::CheckComplement,
+                    &[(lbrace2, EPS_LABEL)],
+                    false,
+                )?;
+                vec![
+                    to_weighted(&r),
+                    to_weighted(&f),
+                    replace,
+                    to_weighted(&l1),
+                    to_weighted(&l2),
+                ]
+            } else {
+                // Contexts are checked on the input before the rewriting.
+                let l1 = make_filter(
+                    &ignore_markers(&lambda, &[lbrace2, rbrace])?,
+                    &extend_sigma(&sigma, &[lbrace2, rbrace]),
+                    MarkerType::Check,
+                    &[(lbrace1, lbrace1)],
+                    false,
+                )?;
+                let l2 = make_filter(
+                    &ignore_markers(&lambda, &[lbrace1, rbrace])?,
+                    &extend_sigma(&sigma, &[lbrace1, rbrace]),
+                    MarkerType::CheckComplement,
+                    &[(lbrace2, lbrace2)],
+                    false,
+                )?;
+                let mut initial_loops = vec![(lbrace2, EPS_LABEL), (rbrace, EPS_LABEL)];
+                if optional {
+                    initial_loops.push((lbrace1, EPS_LABEL));
+                }
+                let replace = make_replace(
+                    &tau,
+                    &sigma,
+                    (lbrace1, EPS_LABEL),
+                    (rbrace, EPS_LABEL),
+                    &initial_loops,
+                    &[
+                        (lbrace1, EPS_LABEL),
+                        (lbrace2, EPS_LABEL),
+                        (rbrace, EPS_LABEL),
+                    ],
+                )?;
+                vec![
+                    to_weighted(&r),
+                    to_weighted(&f),
+                    to_weighted(&l1),
+                    to_weighted(&l2),
+                    replace,
+                ]
+            }
+        }
+        CdrewriteDirection::RightToLeft => {
+            let (lbrace, rbrace1, rbrace2) = (m1, m2, m3);
+            // Inserts `<` after each occurrence of lambda.
+            let l = make_filter(
+                &lambda,
+                &sigma,
+                MarkerType::Mark,
+                &[(EPS_LABEL, lbrace)],
+                false,
+            )?;
+            // Inserts `>1` or `>2` after each occurrence of phi preceded by `<`.
+            let mut lbrace_phi: VectorFst<_> = acceptor(&[lbrace], TropicalWeight::one());
+            concat(&mut lbrace_phi, &ignore_inner_markers(&phi, &[lbrace])?)?;
+            let f = make_filter(
+                &lbrace_phi,
+                &extend_sigma(&sigma, &[lbrace]),
+                MarkerType::Mark,
+                &[(EPS_LABEL, rbrace1), (EPS_LABEL, rbrace2)],
+                false,
+            )?;
+            // Rewrites phi between `<` and `>1`, removes `<`.
+            let mut initial_loops = vec![(rbrace2, rbrace2), (lbrace, EPS_LABEL)];
+            if optional {
+                initial_loops.push((rbrace1, rbrace1));
+            }
+            let replace = make_replace(
+                &tau,
+                &sigma,
+                (lbrace, EPS_LABEL),
+                (rbrace1, rbrace1),
+                &initial_loops,
+                // Markers inside the rewritten region are no longer checked, only `>1`
+                // is kept to avoid redundant paths.
+                &[(lbrace, EPS_LABEL), (rbrace1, EPS_LABEL)],
+            )?;
+            // `>1` must be followed by rho, `>2` must not.
+            let r1 = make_filter(
+                &ignore_markers(&rho, &[rbrace2])?,
+                &extend_sigma(&sigma, &[rbrace2]),
+                MarkerType::Check,
+                &[(rbrace1, EPS_LABEL)],
+                true,
+            )?;
+            let r2 = make_filter(
+                &rho,
+                &sigma,
+                MarkerType::CheckComplement,
+                &[(rbrace2, EPS_LABEL)],
+                true,
+            )?;
+            vec![
+                to_weighted(&l),
+                to_weighted(&f),
+                replace,
+                to_weighted(&r1),
+                to_weighted(&r2),
+            ]
+        }
+    };
+
+    let mut fsts = fsts.into_iter();
+    let mut res = fsts.next().unwrap();
+    for mut fst in fsts {
+        tr_sort(&mut res, olabel_compare);
+        tr_sort(&mut fst, ilabel_compare);
+        res = compose(Arc::new(res), Arc::new(fst))?;
+    }
+    Ok(res)
+}
+
+/// Sorted non-epsilon labels appearing on the transitions of an FST.
+fn collect_labels<W: Semiring, F: ExpandedFst<W>>(fst: &F) -> Vec<Label> {
+    let mut labels = vec![];
+    for s in fst.states_iter() {
+        for tr in unsafe { fst.get_trs_unchecked(s).trs() } {
+            labels.push(tr.ilabel);
+            labels.push(tr.olabel);
+        }
+    }
+    labels.retain(|l| *l != EPS_LABEL);
+    labels.sort();
+    labels.dedup();
+    labels
+}
+
+fn extend_sigma(sigma: &[Label], markers: &[Label]) -> Vec<Label> {
+    let mut sigma = sigma.to_vec();
+    sigma.extend_from_slice(markers);
+    sigma
+}
+
+/// Copies an FST mapping its weights. Zero final weights are not copied.
+fn copy_fst<W1, W2, F, M>(fst: &F, map_weight: M) -> VectorFst<W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+    F: ExpandedFst<W1>,
+    M: Fn(&W1) -> W2,
+{
+    let mut res = VectorFst::new();
+    res.add_states(fst.num_states());
+    for s in fst.states_iter() {
+        unsafe {
+            for tr in fst.get_trs_unchecked(s).trs() {
+                res.add_tr_unchecked(
+                    s,
+                    Tr::new(tr.ilabel, tr.olabel, map_weight(&tr.weight), tr.nextstate),
+                );
+            }
+            if let Some(w) = fst.final_weight_unchecked(s) {
+                if !w.is_zero() {
+                    res.set_final_unchecked(s, map_weight(&w));
+                }
+            }
+        }
+    }
+    if let Some(start) = fst.start() {
+        unsafe { res.set_start_unchecked(start) };
+    }
+    res
+}
+
+fn to_weighted<W: Semiring>(fst: &VectorFst<TropicalWeight>) -> VectorFst<W> {
+    copy_fst(fst, |_| W::one())
+}
+
+/// Adds self-loops on the markers at each state so that they are ignored when matching.
+fn ignore_markers(
+    fst: &VectorFst<TropicalWeight>,
+    markers: &[Label],
+) -> Result<VectorFst<TropicalWeight>> {
+    let mut res = fst.clone();
+    for s in 0..res.num_states() {
+        for marker in markers {
+            res.add_tr(s, Tr::new(*marker, *marker, TropicalWeight::one(), s))?;
+        }
+    }
+    Ok(res)
+}
+
+/// Same as `ignore_markers` but the markers are only allowed between two labels, so that
+/// a match can neither start nor end with a marker.
+fn ignore_inner_markers(
+    fst: &VectorFst<TropicalWeight>,
+    markers: &[Label],
+) -> Result<VectorFst<TropicalWeight>> {
+    let mut res = fst.clone();
+    let start = match fst.start() {
+        Some(start) => start,
+        None => return Ok(res),
+    };
+    let num_states = fst.num_states();
+    // Copy of the states reached after reading markers : they are not final.
+    res.add_states(num_states);
+    for s in 0..num_states {
+        let pending = s + num_states;
+        for tr in unsafe { fst.get_trs_unchecked(s).trs() } {
+            res.add_tr(pending, tr.clone())?;
+        }
+        for marker in markers {
+            res.add_tr(s, Tr::new(*marker, *marker, TropicalWeight::one(), pending))?;
+            res.add_tr(
+                pending,
+                Tr::new(*marker, *marker, TropicalWeight::one(), pending),
+            )?;
+        }
+    }
+    // New start state without markers.
+    let new_start = res.add_state();
+    for tr in unsafe { fst.get_trs_unchecked(start).trs() } {
+        res.add_tr(new_start, tr.clone())?;
+    }
+    if let Some(weight) = fst.final_weight(start)? {
+        res.set_final(new_start, weight)?;
+    }
+    res.set_start(new_start)?;
+    Ok(res)
+}
+
+/// Builds the deterministic acceptor of `sigma* beta` (or of its reverse) and turns it into
+/// a transducer inserting or checking the markers.
+fn make_filter(
+    beta: &VectorFst<TropicalWeight>,
+    sigma: &[Label],
+    marker_type: MarkerType,
+    markers: &[(Label, Label)],
+    reverse_filter: bool,
+) -> Result<VectorFst<TropicalWeight>> {
+    let mut ufilter = VectorFst::new();
+    let s = ufilter.add_state();
+    ufilter.set_start(s)?;
+    ufilter.set_final(s, TropicalWeight::one())?;
+    for label in sigma {
+        ufilter.add_tr(s, Tr::new(*label, *label, TropicalWeight::one(), s))?;
+    }
+    if reverse_filter {
+        let reversed_beta: VectorFst<_> = reverse(beta)?;
+        concat(&mut ufilter, &reversed_beta)?;
+    } else {
+        concat(&mut ufilter, beta)?;
+    }
+    rm_epsilon(&mut ufilter)?;
+    let mut filter: VectorFst<_> =
+        determinize(Arc::new(ufilter), DeterminizeType::DeterminizeFunctional)?;
+    minimize(&mut filter, false)?;
+    make_marker(&mut filter, marker_type, markers)?;
+    if reverse_filter {
+        filter = reverse(&filter)?;
+    }
+    Ok(filter)
+}
+
+fn make_marker(
+    fst: &mut VectorFst<TropicalWeight>,
+    marker_type: MarkerType,
+    markers: &[(Label, Label)],
+) -> Result<()> {
+    let num_states = fst.num_states();
+    for s in 0..num_states {
+        let is_final = fst.is_final(s)?;
+        match marker_type {
+            MarkerType::Mark => {
+                if is_final {
+                    // The markers are inserted before leaving the state.
+                    let new_state = fst.add_state();
+                    fst.set_final(new_state, TropicalWeight::one())?;
+                    for tr in fst.pop_trs(s)? {
+                        fst.add_tr(new_state, tr)?;
+                    }
+                    fst.delete_final_weight(s)?;
+                    for (ilabel, olabel) in markers {
+                        fst.add_tr(
+                            s,
+                            Tr::new(*ilabel, *olabel, TropicalWeight::one(), new_state),
+                        )?;
+                    }
+                    continue;
+                }
+            }
+            MarkerType::Check | MarkerType::CheckComplement => {
+                if is_final == (marker_type == MarkerType::Check) {
+                    for (ilabel, olabel) in markers {
+                        fst.add_tr(s, Tr::new(*ilabel, *olabel, TropicalWeight::one(), s))?;
+                    }
+                }
+            }
+        }
+        fst.set_final(s, TropicalWeight::one())?;
+    }
+    Ok(())
+}
+
+/// Builds the transducer applying `tau` on the regions delimited by the `initial` and `final_`
+/// markers and copying the rest of the string.
+fn make_replace<W: Semiring>(
+    tau: &VectorFst<W>,
+    sigma: &[Label],
+    initial: (Label, Label),
+    final_: (Label, Label),
+    initial_loops: &[(Label, Label)],
+    all_loops: &[(Label, Label)],
+) -> Result<VectorFst<W>> {
+    let mut region = tau.clone();
+    let num_states = region.num_states();
+    let start = match region.start() {
+        Some(start) => start,
+        None => region.add_state(),
+    };
+    let new_start = region.add_state();
+    let new_final = region.add_state();
+    for s in 0..num_states {
+        for (ilabel, olabel) in all_loops {
+            region.add_tr(s, Tr::new(*ilabel, *olabel, W::one(), s))?;
+        }
+        if let Some(weight) = region.take_final_weight(s)? {
+            region.add_tr(s, Tr::new(final_.0, final_.1, weight, new_final))?;
+        }
+    }
+    region.add_tr(new_start, Tr::new(initial.0, initial.1, W::one(), start))?;
+    region.set_start(new_start)?;
+    region.set_final(new_final, W::one())?;
+
+    let mut single = VectorFst::new();
+    let s0 = single.add_state();
+    let s1 = single.add_state();
+    single.set_start(s0)?;
+    single.set_final(s1, W::one())?;
+    for label in sigma {
+        single.add_tr(s0, Tr::new(*label, *label, W::one(), s1))?;
+    }
+    for (ilabel, olabel) in initial_loops {
+        single.add_tr(s0, Tr::new(*ilabel, *olabel, W::one(), s1))?;
+    }
+
+    union(&mut region, &single)?;
+    closure(&mut region, ClosureType::ClosureStar);
+    Ok(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_traits::PathsIterator;
+    use crate::utils::transducer;
+
+    fn sigma_star(labels: &[Label]) -> Result<VectorFst<TropicalWeight>> {
+        let mut fst = VectorFst::new();
+        let s = fst.add_state();
+        fst.set_start(s)?;
+        fst.set_final(s, TropicalWeight::one())?;
+        for label in labels {
+            fst.add_tr(s, Tr::new(*label, *label, TropicalWeight::one(), s))?;
+        }
+        Ok(fst)
+    }
+
+    fn apply(rule: &VectorFst<TropicalWeight>, input: &[Label]) -> Result<Vec<Vec<Label>>> {
+        let input: VectorFst<TropicalWeight> = acceptor(input, TropicalWeight::one());
+        let output: VectorFst<TropicalWeight> = compose(Arc::new(input), Arc::new(rule.clone()))?;
+        let mut outputs: Vec<_> = output.paths_iter().map(|p| p.olabels).collect();
+        outputs.sort();
+        outputs.dedup();
+        Ok(outputs)
+    }
+
+    fn rule(
+        lambda: &[Label],
+        rho: &[Label],
+        direction: CdrewriteDirection,
+        mode: CdrewriteMode,
+    ) -> Result<VectorFst<TropicalWeight>> {
+        // Rewrites 1 into 2.
+        let tau = transducer(&[1], &[2], TropicalWeight::one());
+        cdrewrite(
+            &tau,
+            &acceptor(lambda, TropicalWeight::one()),
+            &acceptor(rho, TropicalWeight::one()),
+            &sigma_star(&[1, 2, 3, 4])?,
+            direction,
+            mode,
+        )
+    }
+
+    #[test]
+    fn test_cdrewrite_obligatory() -> Result<()> {
+        let rule = rule(
+            &[3],
+            &[4],
+            CdrewriteDirection::LeftToRight,
+            CdrewriteMode::Obligatory,
+        )?;
+        assert_eq!(apply(&rule, &[3, 1, 4])?, vec![vec![3, 2, 4]]);
+        assert_eq!(apply(&rule, &[3, 1, 1, 4])?, vec![vec![3, 1, 1, 4]]);
+        assert_eq!(
+            apply(&rule, &[3, 1, 4, 3, 1, 4, 1])?,
+            vec![vec![3, 2, 4, 3, 2, 4, 1]]
+        );
+        assert_eq!(apply(&rule, &[])?, vec![Vec::<Label>::new()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdrewrite_optional() -> Result<()> {
+        let rule = rule(
+            &[3],
+            &[],
+            CdrewriteDirection::LeftToRight,
+            CdrewriteMode::Optional,
+        )?;
+        assert_eq!(
+            apply(&rule, &[3, 1, 1])?,
+            vec![vec![3, 1, 1], vec![3, 2, 1]]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdrewrite_directions() -> Result<()> {
+        let obligatory = CdrewriteMode::Obligatory;
+        // 1 -> 2 / 1 __
+        let ltr = rule(&[1], &[], CdrewriteDirection::LeftToRight, obligatory)?;
+        let rtl = rule(&[1], &[], CdrewriteDirection::RightToLeft, obligatory)?;
+        let sim = rule(&[1], &[], CdrewriteDirection::Simultaneous, obligatory)?;
+        assert_eq!(apply(&ltr, &[1, 1, 1])?, vec![vec![1, 2, 1]]);
+        assert_eq!(apply(&rtl, &[1, 1, 1])?, vec![vec![1, 2, 2]]);
+        assert_eq!(apply(&sim, &[1, 1, 1])?, vec![vec![1, 2, 2]]);
+
+        // 1 -> 2 / __ 1
+        let ltr = rule(&[], &[1], CdrewriteDirection::LeftToRight, obligatory)?;
+        let rtl = rule(&[], &[1], CdrewriteDirection::RightToLeft, obligatory)?;
+        let sim = rule(&[], &[1], CdrewriteDirection::Simultaneous, obligatory)?;
+        assert_eq!(apply(&ltr, &[1, 1, 1])?, vec![vec![2, 2, 1]]);
+        assert_eq!(apply(&rtl, &[1, 1, 1])?, vec![vec![1, 2, 1]]);
+        assert_eq!(apply(&sim, &[1, 1, 1])?, vec![vec![2, 2, 1]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdrewrite_weighted_tau() -> Result<()> {
+        let tau = transducer(&[1, 1], &[2], TropicalWeight::new(1.5));
+        let rule = cdrewrite(
+            &tau,
+            &acceptor(&[], TropicalWeight::one()),
+            &acceptor(&[], TropicalWeight::one()),
+            &sigma_star(&[1, 2, 3])?,
+            CdrewriteDirection::LeftToRight,
+            CdrewriteMode::Obligatory,
+        )?;
+        let input: VectorFst<TropicalWeight> = acceptor(&[3, 1, 1, 1], TropicalWeight::one());
+        let output: VectorFst<TropicalWeight> = compose(Arc::new(input), Arc::new(rule))?;
+        let paths: Vec<_> = output.paths_iter().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].olabels, vec![3, 2, 1]);
+        assert_eq!(paths[0].weight, TropicalWeight::new(1.5));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdrewrite_overlapping_matches() -> Result<()> {
+        // 1 1 -> 2 without context.
+        let tau = transducer(&[1, 1], &[2], TropicalWeight::one());
+        let eps = acceptor(&[], TropicalWeight::one());
+        let expected = vec![
+            (CdrewriteDirection::LeftToRight, vec![2, 1]),
+            (CdrewriteDirection::RightToLeft, vec![1, 2]),
+            (CdrewriteDirection::Simultaneous, vec![2, 1]),
+        ];
+        for (direction, output) in expected {
+            let rule = cdrewrite(
+                &tau,
+                &eps,
+                &eps,
+                &sigma_star(&[1, 2])?,
+                direction,
+                CdrewriteMode::Obligatory,
+            )?;
+            assert_eq!(apply(&rule, &[1, 1, 1])?, vec![output]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdrewrite_missing_label() -> Result<()> {
+        let tau: VectorFst<TropicalWeight> = transducer(&[1], &[5], TropicalWeight::one());
+        let eps: VectorFst<TropicalWeight> = acceptor(&[], TropicalWeight::one());
+        assert!(cdrewrite(
+            &tau,
+            &eps,
+            &eps,
+            &sigma_star(&[1, 2])?,
+            CdrewriteDirection::LeftToRight,
+            CdrewriteMode::Obligatory,
+        )
+        .is_err());
+        Ok(())
+    }
+}
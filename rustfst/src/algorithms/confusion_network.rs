@@ -0,0 +1,521 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::ops::Bound::{Excluded, Unbounded};
+
+use anyhow::{bail, Result};
+use num_traits::AsPrimitive;
+
+use crate::algorithms::weight_converters::FloatWeightConverter;
+use crate::algorithms::{posteriors, top_sort, weight_convert};
+use crate::fst_impls::VectorFst;
+use crate::fst_properties::FstProperties;
+use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+use crate::semirings::{LogWeight, Semiring};
+use crate::trs::Trs;
+use crate::{Label, StateId, Tr, EPS_LABEL, KDELTA};
+
+/// Fixed size set of indices.
+#[derive(Debug, Clone, Default)]
+struct BitSet(Vec<u64>);
+
+impl BitSet {
+    fn new(size: usize) -> Self {
+        BitSet(vec![0; size / 64 + 1])
+    }
+
+    fn insert(&mut self, idx: usize) {
+        self.0[idx / 64] |= 1 << (idx % 64);
+    }
+
+    fn contains(&self, idx: usize) -> bool {
+        self.0[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn union_with(&mut self, other: &BitSet) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a |= *b;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(block_idx, block)| {
+            (0..64)
+                .filter(move |bit| block & (1 << bit) != 0)
+                .map(move |bit| block_idx * 64 + bit)
+        })
+    }
+}
+
+/// Set of trs of the lattice that end up in the same bin of the confusion network.
+#[derive(Debug, Clone, Default)]
+struct Cluster {
+    /// Words of the cluster with their posteriors.
+    words: Vec<(Label, f32)>,
+    posterior: f32,
+    /// Average position of the trs of the cluster, weighted by their posteriors.
+    position: f32,
+}
+
+impl Cluster {
+    fn merge(&mut self, other: &Cluster) {
+        for (word, posterior) in &other.words {
+            match self.words.iter_mut().find(|(w, _)| w == word) {
+                Some((_, p)) => *p += posterior,
+                None => self.words.push((*word, *posterior)),
+            }
+        }
+        let posterior = self.posterior + other.posterior;
+        self.position =
+            (self.position * self.posterior + other.position * other.posterior) / posterior;
+        self.posterior = posterior;
+    }
+}
+
+/// Cluster sorted by position, then by index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PositionKey(f32, usize);
+
+impl Eq for PositionKey {}
+
+impl PartialOrd for PositionKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PositionKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_f32(self.0, other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// Pair of clusters that may be merged, `left` being before `right`. The pair is outdated as
+/// soon as one of the clusters changes, i.e when its version is incremented.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CandidatePair {
+    distance: f32,
+    left: usize,
+    right: usize,
+    versions: (usize, usize),
+}
+
+impl Eq for CandidatePair {}
+
+impl PartialOrd for CandidatePair {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CandidatePair {
+    /// `BinaryHeap` is a max-heap : the greatest pair is the closest one.
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_f32(other.distance, self.distance)
+            .then((other.left, other.right).cmp(&(self.left, self.right)))
+    }
+}
+
+/// Clusters ordered by the precedence of their trs in the lattice.
+///
+/// The closest pair of clusters that can be merged is always the pair of a cluster with the
+/// closest cluster after it that can be merged with it. These pairs are kept in a heap and
+/// updated incrementally : a pair outdated by a merge is only recomputed when it reaches the
+/// top of the heap, and the merged cluster adds its pairs with its closest neighbours.
+struct Clustering {
+    clusters: Vec<Cluster>,
+    alive: Vec<bool>,
+    versions: Vec<usize>,
+    /// Alive clusters sorted by position.
+    sorted: BTreeSet<PositionKey>,
+    /// `successors[i]` contains `j` if a tr of the cluster `i` precedes a tr of the cluster
+    /// `j` on a path of the lattice. The relation is kept transitively closed.
+    successors: Vec<BitSet>,
+    /// Inverse relation of `successors`.
+    predecessors: Vec<BitSet>,
+    candidates: BinaryHeap<CandidatePair>,
+    /// If true, only the clusters made of the same single word can be merged.
+    same_word: bool,
+}
+
+impl Clustering {
+    fn key(&self, i: usize) -> PositionKey {
+        PositionKey(self.clusters[i].position, i)
+    }
+
+    fn can_merge(&self, i: usize, j: usize) -> bool {
+        !self.successors[i].contains(j)
+            && !self.successors[j].contains(i)
+            && (!self.same_word || self.clusters[i].words[0].0 == self.clusters[j].words[0].0)
+    }
+
+    fn push_candidate(&mut self, left: usize, right: usize) {
+        self.candidates.push(CandidatePair {
+            distance: self.clusters[right].position - self.clusters[left].position,
+            left,
+            right,
+            versions: (self.versions[left], self.versions[right]),
+        });
+    }
+
+    /// Adds the pair of the cluster `i` with the closest cluster after it that can be merged.
+    fn push_next_candidate(&mut self, i: usize) {
+        let next = self
+            .sorted
+            .range((Excluded(self.key(i)), Unbounded))
+            .find(|k| self.can_merge(i, k.1));
+        if let Some(&PositionKey(_, j)) = next {
+            self.push_candidate(i, j);
+        }
+    }
+
+    /// Adds the pair of the cluster `i` with the closest cluster before it that can be merged.
+    fn push_previous_candidate(&mut self, i: usize) {
+        let previous = self
+            .sorted
+            .range((Unbounded, Excluded(self.key(i))))
+            .rev()
+            .find(|k| self.can_merge(k.1, i));
+        if let Some(&PositionKey(_, j)) = previous {
+            self.push_candidate(j, i);
+        }
+    }
+
+    /// Starts a clustering phase, where the clusters can be merged if `same_word` allows it.
+    fn start_phase(&mut self, same_word: bool) {
+        self.same_word = same_word;
+        self.candidates.clear();
+        let alive: Vec<_> = self.sorted.iter().map(|k| k.1).collect();
+        for i in alive {
+            self.push_next_candidate(i);
+        }
+    }
+
+    /// Finds the two closest clusters that can be merged.
+    fn closest_pair(&mut self) -> Option<(usize, usize)> {
+        while let Some(pair) = self.candidates.pop() {
+            let (left, right) = (pair.left, pair.right);
+            if !self.alive[left] {
+                continue;
+            }
+            if self.alive[right]
+                && pair.versions == (self.versions[left], self.versions[right])
+                && self.can_merge(left, right)
+            {
+                return Some((left, right));
+            }
+            self.push_next_candidate(left);
+        }
+        None
+    }
+
+    /// Merges the cluster `b` into the cluster `a`.
+    fn merge(&mut self, a: usize, b: usize) {
+        let (key_a, key_b) = (self.key(a), self.key(b));
+        self.sorted.remove(&key_a);
+        self.sorted.remove(&key_b);
+        let cluster_b = std::mem::take(&mut self.clusters[b]);
+        self.clusters[a].merge(&cluster_b);
+        self.alive[b] = false;
+        self.versions[a] += 1;
+        self.sorted.insert(self.key(a));
+
+        // The clusters preceding `a` or `b` now precede `a` and the clusters following them.
+        let mut preceding = std::mem::take(&mut self.predecessors[a]);
+        preceding.union_with(&self.predecessors[b]);
+        let mut following = std::mem::take(&mut self.successors[a]);
+        following.union_with(&self.successors[b]);
+        let alive = &self.alive;
+        for x in preceding.iter().filter(|x| alive[*x]) {
+            self.successors[x].union_with(&following);
+            self.successors[x].insert(a);
+        }
+        for y in following.iter().filter(|y| alive[*y]) {
+            self.predecessors[y].union_with(&preceding);
+            self.predecessors[y].insert(a);
+        }
+        self.predecessors[a] = preceding;
+        self.successors[a] = following;
+
+        self.push_previous_candidate(a);
+        self.push_next_candidate(a);
+    }
+}
+
+fn cmp_f32(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// Turns an acyclic lattice into a confusion network, also called a sausage : a linear FST
+/// where each bin is a set of alternative words weighted by their posterior probabilities.
+///
+/// The words are the output labels of the lattice and its weights are interpreted as negative
+/// log probabilities. The trs of the lattice are clustered following the algorithm of Mangu et
+/// al. : two clusters can only be merged if none of their trs precede each other on a path of the
+/// lattice. Clusters of the same word are merged first, then the clusters of different words.
+/// As the lattice has no time information, the closest clusters are the ones with the closest
+/// expected position, i.e the expected number of words before them computed in topological
+/// order.
+///
+/// Each tr of the output goes from bin `i` to bin `i + 1` and is weighted by the negative log
+/// posterior of its word. An epsilon tr carries the probability of not emitting any word in a
+/// bin. Within a bin, the trs are sorted by decreasing posterior.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::confusion_network;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+/// # use rustfst::semirings::{LogWeight, Semiring};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// // Paths `1 2` with a probability of 0.75 and `3 2` with a probability of 0.25.
+/// let mut lattice = VectorFst::<LogWeight>::new();
+/// lattice.add_states(4);
+/// lattice.set_start(0)?;
+/// lattice.set_final(3, LogWeight::one())?;
+/// lattice.add_tr(0, Tr::new(1, 1, LogWeight::new(-(0.75f32).ln()), 1))?;
+/// lattice.add_tr(0, Tr::new(3, 3, LogWeight::new(-(0.25f32).ln()), 2))?;
+/// lattice.add_tr(1, Tr::new(2, 2, LogWeight::one(), 3))?;
+/// lattice.add_tr(2, Tr::new(2, 2, LogWeight::one(), 3))?;
+///
+/// let sausage: VectorFst<LogWeight> = confusion_network(&lattice)?;
+/// assert_eq!(sausage.num_states(), 3);
+/// let bin = sausage.get_trs(0)?;
+/// assert_eq!((bin[0].olabel, bin[1].olabel), (1, 3));
+/// assert_eq!(sausage.get_trs(1)?[0].olabel, 2);
+/// # Ok(())
+/// # }
+/// ```
+pub fn confusion_network<W, F1, F2>(lattice: &F1) -> Result<F2>
+where
+    W: Semiring,
+    W::Type: AsPrimitive<f32>,
+    f32: AsPrimitive<W::Type>,
+    F1: ExpandedFst<W>,
+    F2: MutableFst<W>,
+{
+    let mut fst: VectorFst<LogWeight> = weight_convert(lattice, &mut FloatWeightConverter {})?;
+    if !fst.properties()?.contains(FstProperties::ACYCLIC) {
+        bail!("confusion_network : the lattice must be acyclic");
+    }
+    top_sort(&mut fst)?;
+    let posteriors = posteriors(&fst)?;
+    let num_states = fst.num_states();
+
+    // Expected number of words before each state, knowing that the path goes through it.
+    let mut positions = vec![0f32; num_states];
+    let mut incoming = vec![0f32; num_states];
+    for s in 0..num_states {
+        if incoming[s] > 0.0 {
+            positions[s] /= incoming[s];
+        }
+        let trs = fst.get_trs(s)?;
+        for (tr, posterior) in trs.trs().iter().zip(posteriors.trs[s].iter()) {
+            let words = if tr.olabel == EPS_LABEL { 0.0 } else { 1.0 };
+            positions[tr.nextstate] += posterior * (positions[s] + words);
+            incoming[tr.nextstate] += posterior;
+        }
+    }
+
+    // States reachable from each state, computed in reverse topological order.
+    let mut reachable: Vec<BitSet> = vec![BitSet::new(num_states); num_states];
+    for s in (0..num_states).rev() {
+        // The states are topologically sorted : the next states come after `s`.
+        let (head, tail) = reachable.split_at_mut(s + 1);
+        head[s].insert(s);
+        let trs = fst.get_trs(s)?;
+        for tr in trs.trs() {
+            head[s].union_with(&tail[tr.nextstate - s - 1]);
+        }
+    }
+
+    // Each tr of the lattice with a word and a non-zero posterior starts in its own cluster.
+    let mut clusters = vec![];
+    let mut endpoints: Vec<(StateId, StateId)> = vec![];
+    for (s, position) in positions.iter().enumerate() {
+        let trs = fst.get_trs(s)?;
+        for (tr, posterior) in trs.trs().iter().zip(posteriors.trs[s].iter()) {
+            if tr.olabel != EPS_LABEL && *posterior > 0.0 {
+                clusters.push(Cluster {
+                    words: vec![(tr.olabel, *posterior)],
+                    posterior: *posterior,
+                    position: *position,
+                });
+                endpoints.push((s, tr.nextstate));
+            }
+        }
+    }
+    let num_clusters = clusters.len();
+    let mut successors = vec![BitSet::new(num_clusters); num_clusters];
+    let mut predecessors = vec![BitSet::new(num_clusters); num_clusters];
+    for (i, (_, nextstate)) in endpoints.iter().enumerate() {
+        for (j, (source, _)) in endpoints.iter().enumerate() {
+            if reachable[*nextstate].contains(*source) {
+                successors[i].insert(j);
+                predecessors[j].insert(i);
+            }
+        }
+    }
+    let mut clustering = Clustering {
+        sorted: (0..num_clusters)
+            .map(|c| PositionKey(clusters[c].position, c))
+            .collect(),
+        clusters,
+        alive: vec![true; num_clusters],
+        versions: vec![0; num_clusters],
+        successors,
+        predecessors,
+        candidates: BinaryHeap::new(),
+        same_word: true,
+    };
+
+    // Intra-word clustering then inter-word clustering.
+    for &same_word in &[true, false] {
+        clustering.start_phase(same_word);
+        while let Some((i, j)) = clustering.closest_pair() {
+            clustering.merge(i, j);
+        }
+    }
+
+    // The remaining clusters are totally ordered.
+    let mut bins: Vec<_> = clustering
+        .sorted
+        .iter()
+        .map(|k| {
+            let num_predecessors = clustering.predecessors[k.1]
+                .iter()
+                .filter(|p| clustering.alive[*p])
+                .count();
+            (num_predecessors, k.1)
+        })
+        .collect();
+    bins.sort();
+
+    let mut sausage = F2::new();
+    let mut state = sausage.add_state();
+    sausage.set_start(state)?;
+    let to_weight = |posterior: f32| W::new((-posterior.ln()).as_());
+    for (_, c) in bins {
+        let cluster = &clustering.clusters[c];
+        let mut words = cluster.words.clone();
+        words.sort_by(|a, b| cmp_f32(b.1, a.1));
+        let next_state = sausage.add_state();
+        for (word, posterior) in words {
+            sausage.add_tr(state, Tr::new(word, word, to_weight(posterior), next_state))?;
+        }
+        let eps_posterior = 1.0 - cluster.posterior;
+        if eps_posterior > KDELTA {
+            sausage.add_tr(
+                state,
+                Tr::new(EPS_LABEL, EPS_LABEL, to_weight(eps_posterior), next_state),
+            )?;
+        }
+        state = next_state;
+    }
+    sausage.set_final(state, W::one())?;
+    Ok(sausage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::semirings::TropicalWeight;
+
+    fn prob(p: f32) -> TropicalWeight {
+        TropicalWeight::new(-p.ln())
+    }
+
+    /// Words and posteriors of each bin.
+    fn bins(sausage: &VectorFst<TropicalWeight>) -> Result<Vec<Vec<(Label, f32)>>> {
+        let mut bins = vec![];
+        for s in 0..sausage.num_states() - 1 {
+            let trs = sausage.get_trs(s)?;
+            let mut bin = vec![];
+            for tr in trs.trs() {
+                assert_eq!(tr.nextstate, s + 1);
+                bin.push((tr.olabel, (-tr.weight.value()).exp()));
+            }
+            bins.push(bin);
+        }
+        Ok(bins)
+    }
+
+    fn assert_bins(actual: &[Vec<(Label, f32)>], expected: &[Vec<(Label, f32)>]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert_eq!(a.len(), e.len(), "{:?}", actual);
+            for ((la, pa), (le, pe)) in a.iter().zip(e.iter()) {
+                assert_eq!(la, le, "{:?}", actual);
+                assert!((pa - pe).abs() < 1e-4, "{:?}", actual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_confusion_network_deletion() -> Result<()> {
+        // Paths `1 2 3` (0.6), `1 3` (0.3) and `4 3` (0.1).
+        let mut lattice = VectorFst::<TropicalWeight>::new();
+        lattice.add_states(5);
+        lattice.set_start(0)?;
+        lattice.set_final(4, TropicalWeight::one())?;
+        lattice.add_tr(0, Tr::new(1, 1, prob(0.9), 1))?;
+        lattice.add_tr(0, Tr::new(4, 4, prob(0.1), 3))?;
+        lattice.add_tr(1, Tr::new(2, 2, prob(2.0 / 3.0), 2))?;
+        lattice.add_tr(1, Tr::new(0, 0, prob(1.0 / 3.0), 3))?;
+        lattice.add_tr(2, Tr::new(3, 3, TropicalWeight::one(), 4))?;
+        lattice.add_tr(3, Tr::new(3, 3, TropicalWeight::one(), 4))?;
+
+        let sausage: VectorFst<TropicalWeight> = confusion_network(&lattice)?;
+        assert_bins(
+            &bins(&sausage)?,
+            &[
+                vec![(1, 0.9), (4, 0.1)],
+                vec![(2, 0.6), (0, 0.4)],
+                vec![(3, 1.0)],
+            ],
+        );
+        assert!(sausage.is_final(3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_confusion_network_same_word_at_different_depths() -> Result<()> {
+        // Paths `1 2 3` (0.5) and `4 3` (0.5) : both `3` are merged.
+        let mut lattice = VectorFst::<TropicalWeight>::new();
+        lattice.add_states(5);
+        lattice.set_start(0)?;
+        lattice.set_final(4, TropicalWeight::one())?;
+        lattice.add_tr(0, Tr::new(1, 1, prob(0.5), 1))?;
+        lattice.add_tr(0, Tr::new(4, 4, prob(0.5), 3))?;
+        lattice.add_tr(1, Tr::new(2, 2, TropicalWeight::one(), 2))?;
+        lattice.add_tr(2, Tr::new(3, 3, TropicalWeight::one(), 4))?;
+        lattice.add_tr(3, Tr::new(3, 3, TropicalWeight::one(), 4))?;
+
+        let sausage: VectorFst<TropicalWeight> = confusion_network(&lattice)?;
+        let bins = bins(&sausage)?;
+        assert_eq!(bins.len(), 3);
+        assert_eq!(bins[2].len(), 1);
+        assert_eq!(bins[2][0].0, 3);
+        assert!((bins[2][0].1 - 1.0).abs() < 1e-4);
+        // The posteriors of each bin sum to one.
+        for bin in bins {
+            let total: f32 = bin.iter().map(|(_, p)| p).sum();
+            assert!((total - 1.0).abs() < 1e-4);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_confusion_network_cyclic() -> Result<()> {
+        let mut lattice = VectorFst::<TropicalWeight>::new();
+        lattice.add_states(1);
+        lattice.set_start(0)?;
+        lattice.set_final(0, TropicalWeight::one())?;
+        lattice.add_tr(0, Tr::new(1, 1, TropicalWeight::one(), 0))?;
+        assert!(confusion_network::<_, _, VectorFst<_>>(&lattice).is_err());
+        Ok(())
+    }
+}
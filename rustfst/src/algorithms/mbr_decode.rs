@@ -0,0 +1,324 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use num_traits::AsPrimitive;
+
+use crate::algorithms::weight_converters::FloatWeightConverter;
+use crate::algorithms::{
+    project, shortest_distance, shortest_paths_iter, top_sort, weight_convert, ProjectType,
+};
+use crate::fst_impls::VectorFst;
+use crate::fst_properties::FstProperties;
+use crate::fst_traits::{CoreFst, ExpandedFst};
+use crate::semirings::{LogWeight, Semiring, TropicalWeight};
+use crate::{Label, Trs, EPS_LABEL};
+
+/// Result of the minimum Bayes risk decoding of a lattice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MbrHypothesis {
+    /// Words of the hypothesis.
+    pub words: Vec<Label>,
+    /// Probability that each word of the hypothesis is wrong, i.e substituted or inserted.
+    pub word_risks: Vec<f32>,
+    /// Expected edit distance between the hypothesis and the strings of the lattice.
+    pub expected_errors: f32,
+}
+
+/// Finds the hypothesis minimizing the expected edit distance to the strings of an acyclic
+/// lattice, among the strings of its `nbest` shortest paths.
+///
+/// The words are the output labels of the lattice and its weights are interpreted as negative
+/// log probabilities. The expected edit distance of each candidate is computed over the whole
+/// lattice and not only over the candidates : the lattice is topologically sorted and a forward
+/// pass in `LogWeight` propagates the edit distance alignments of the candidate with the
+/// prefixes of the paths, merging the prefixes with the same alignment. The backward
+/// `shortest_distance` gives the total weight used to normalize the probabilities and prunes
+/// the states from which no final state can be reached.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::mbr_decode;
+/// # use rustfst::algorithms::union::union;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::acceptor;
+/// # fn main() -> Result<()> {
+/// let prob = |p: f32| TropicalWeight::new(-p.ln());
+/// let mut lattice: VectorFst<TropicalWeight> = acceptor(&[1, 2, 3], prob(0.4));
+/// let fst: VectorFst<TropicalWeight> = acceptor(&[4, 2, 5], prob(0.3));
+/// union(&mut lattice, &fst)?;
+/// let fst: VectorFst<TropicalWeight> = acceptor(&[1, 2, 5], prob(0.3));
+/// union(&mut lattice, &fst)?;
+///
+/// // `1 2 3` is the most likely string but `1 2 5` is closer to the other ones.
+/// let hypothesis = mbr_decode(&lattice, 10)?;
+/// assert_eq!(hypothesis.words, vec![1, 2, 5]);
+/// assert!((hypothesis.expected_errors - 0.7).abs() < 1e-4);
+/// assert!((hypothesis.word_risks[2] - 0.4).abs() < 1e-4);
+/// # Ok(())
+/// # }
+/// ```
+pub fn mbr_decode<W, F>(lattice: &F, nbest: usize) -> Result<MbrHypothesis>
+where
+    W: Semiring,
+    W::Type: AsPrimitive<f32>,
+    F: ExpandedFst<W>,
+{
+    let mut fst: VectorFst<LogWeight> = weight_convert(lattice, &mut FloatWeightConverter {})?;
+    if !fst.properties()?.contains(FstProperties::ACYCLIC) {
+        bail!("mbr_decode : the lattice must be acyclic");
+    }
+    project(&mut fst, ProjectType::ProjectOutput);
+    // The forward pass visits the states by increasing id.
+    top_sort(&mut fst)?;
+    let mut backward = shortest_distance(&fst, true)?;
+    backward.resize(fst.num_states(), LogWeight::zero());
+    let total_weight = match fst.start() {
+        Some(start) => backward[start],
+        None => LogWeight::zero(),
+    };
+
+    let tropical_fst: VectorFst<TropicalWeight> =
+        weight_convert(&fst, &mut FloatWeightConverter {})?;
+    let candidates: Vec<Vec<Label>> = shortest_paths_iter(&tropical_fst, true)?
+        .take(nbest)
+        .map(|path| path.olabels)
+        .collect();
+    if total_weight.is_zero() || candidates.is_empty() {
+        bail!("mbr_decode : the lattice has no successful path");
+    }
+
+    let mut best: Option<(&Vec<Label>, f32)> = None;
+    for candidate in &candidates {
+        let (expected_errors, _) = expected_alignment(&fst, &backward, candidate, false)?;
+        // The candidates come by increasing weight, the first one is kept on ties.
+        match best {
+            Some((_, best_errors)) if best_errors <= expected_errors => {}
+            _ => best = Some((candidate, expected_errors)),
+        }
+    }
+    let words = best.unwrap().0.clone();
+    let (expected_errors, word_risks) = expected_alignment(&fst, &backward, &words, true)?;
+    Ok(MbrHypothesis {
+        words,
+        word_risks,
+        expected_errors,
+    })
+}
+
+/// Alignment of a hypothesis with a prefix of a path : for each prefix of the hypothesis, its
+/// edit distance to the prefix of the path and which of its words are substituted or inserted.
+type AlignmentRow = Vec<(usize, Vec<bool>)>;
+
+/// Alignment of a hypothesis of `n` words with an empty prefix, all its words are inserted.
+/// The wrong words are only tracked if `with_risks` is set.
+fn start_row(n: usize, with_risks: bool) -> AlignmentRow {
+    (0..=n)
+        .map(|i| {
+            let wrong = if with_risks {
+                (0..n).map(|k| k < i).collect()
+            } else {
+                vec![]
+            };
+            (i, wrong)
+        })
+        .collect()
+}
+
+/// Extends the alignment of `hypothesis` with a prefix of a path by the next `word` of the
+/// path. On ties, matches and substitutions are preferred, then skipping the word of the path.
+fn align_word(row: &[(usize, Vec<bool>)], hypothesis: &[Label], word: Label) -> AlignmentRow {
+    let mut next: AlignmentRow = Vec::with_capacity(row.len());
+    next.push((row[0].0 + 1, row[0].1.clone()));
+    for (i, h) in hypothesis.iter().enumerate() {
+        let substitution = usize::from(*h != word);
+        let diagonal = row[i].0 + substitution;
+        let skip = row[i + 1].0 + 1;
+        let insertion = next[i].0 + 1;
+        let (errors, mut wrong, is_wrong) = if diagonal <= skip && diagonal <= insertion {
+            (diagonal, row[i].1.clone(), substitution == 1)
+        } else if skip <= insertion {
+            (skip, row[i + 1].1.clone(), false)
+        } else {
+            (insertion, next[i].1.clone(), true)
+        };
+        if is_wrong && !wrong.is_empty() {
+            wrong[i] = true;
+        }
+        next.push((errors, wrong));
+    }
+    next
+}
+
+/// Computes the expected edit distance between `hypothesis` and the strings of the
+/// topologically sorted `fst` whose `backward` distances are given, and if `with_risks` the
+/// probability of each word of the hypothesis to be wrong.
+fn expected_alignment(
+    fst: &VectorFst<LogWeight>,
+    backward: &[LogWeight],
+    hypothesis: &[Label],
+    with_risks: bool,
+) -> Result<(f32, Vec<f32>)> {
+    let n = hypothesis.len();
+    let mut expected_errors = 0.0;
+    let mut word_risks = vec![0.0; n];
+    let start = match fst.start() {
+        Some(start) => start,
+        None => return Ok((expected_errors, word_risks)),
+    };
+    let total_weight = backward[start];
+
+    let mut forward: Vec<HashMap<AlignmentRow, LogWeight>> = vec![HashMap::new(); fst.num_states()];
+    forward[start].insert(start_row(n, with_risks), LogWeight::one());
+    for state in start..fst.num_states() {
+        let rows = std::mem::take(&mut forward[state]);
+        let final_weight = fst.final_weight(state)?;
+        let trs = fst.get_trs(state)?;
+        for (row, weight) in rows {
+            if let Some(final_weight) = &final_weight {
+                let p = (total_weight.value() - weight.times(final_weight)?.value()).exp();
+                let (errors, wrong) = &row[n];
+                expected_errors += p * *errors as f32;
+                for (risk, is_wrong) in word_risks.iter_mut().zip(wrong) {
+                    if *is_wrong {
+                        *risk += p;
+                    }
+                }
+            }
+            for tr in trs.trs() {
+                if backward[tr.nextstate].is_zero() {
+                    continue;
+                }
+                let next_row = if tr.olabel == EPS_LABEL {
+                    row.clone()
+                } else {
+                    align_word(&row, hypothesis, tr.olabel)
+                };
+                let next_weight = weight.times(tr.weight)?;
+                match forward[tr.nextstate].entry(next_row) {
+                    Entry::Occupied(mut e) => e.get_mut().plus_assign(next_weight)?,
+                    Entry::Vacant(e) => {
+                        e.insert(next_weight);
+                    }
+                }
+            }
+        }
+    }
+    Ok((expected_errors, word_risks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::union::union;
+    use crate::fst_traits::MutableFst;
+    use crate::utils::acceptor;
+    use crate::Tr;
+
+    fn prob(p: f32) -> TropicalWeight {
+        TropicalWeight::new(-p.ln())
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    fn edit_alignment(hypothesis: &[Label], reference: &[Label]) -> (usize, Vec<bool>) {
+        let mut row = start_row(hypothesis.len(), true);
+        for word in reference {
+            row = align_word(&row, hypothesis, *word);
+        }
+        row.pop().unwrap()
+    }
+
+    #[test]
+    fn test_edit_alignment() {
+        assert_eq!(edit_alignment(&[1, 2, 3], &[1, 2, 3]), (0, vec![false; 3]));
+        assert_eq!(
+            edit_alignment(&[1, 2, 3], &[1, 3]),
+            (1, vec![false, true, false])
+        );
+        assert_eq!(edit_alignment(&[1, 3], &[1, 2, 3]), (1, vec![false, false]));
+        assert_eq!(edit_alignment(&[4, 2], &[1, 2, 3]), (2, vec![true, false]));
+        assert_eq!(edit_alignment(&[], &[1, 2]), (2, vec![]));
+    }
+
+    #[test]
+    fn test_mbr_decode_sums_paths_of_same_string() -> Result<()> {
+        // `1 2` has two paths with a total probability of 0.5, `3 4` has a probability of 0.4
+        // and `5` of 0.1.
+        let mut lattice = VectorFst::<TropicalWeight>::new();
+        lattice.add_states(4);
+        lattice.set_start(0)?;
+        lattice.set_final(3, TropicalWeight::one())?;
+        lattice.add_tr(0, Tr::new(1, 1, prob(0.3), 1))?;
+        lattice.add_tr(0, Tr::new(1, 1, prob(0.2), 1))?;
+        lattice.add_tr(1, Tr::new(2, 2, TropicalWeight::one(), 3))?;
+        lattice.add_tr(0, Tr::new(3, 3, prob(0.4), 2))?;
+        lattice.add_tr(2, Tr::new(4, 4, TropicalWeight::one(), 3))?;
+        lattice.add_tr(0, Tr::new(5, 5, prob(0.1), 3))?;
+
+        let hypothesis = mbr_decode(&lattice, 10)?;
+        assert_eq!(hypothesis.words, vec![1, 2]);
+        assert_close(hypothesis.expected_errors, 0.4 * 2.0 + 0.1 * 2.0);
+        assert_close(hypothesis.word_risks[0], 0.5);
+        assert_close(hypothesis.word_risks[1], 0.5);
+
+        // Only `3 4` is a candidate but its risk is computed over all the strings.
+        let hypothesis = mbr_decode(&lattice, 1)?;
+        assert_eq!(hypothesis.words, vec![3, 4]);
+        assert_close(hypothesis.expected_errors, 0.5 * 2.0 + 0.1 * 2.0);
+        assert_close(hypothesis.word_risks[0], 0.6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mbr_decode_one_best() -> Result<()> {
+        let mut lattice: VectorFst<TropicalWeight> = acceptor(&[1, 2, 3], prob(0.4));
+        let fst: VectorFst<TropicalWeight> = acceptor(&[4, 2, 5], prob(0.3));
+        union(&mut lattice, &fst)?;
+        let fst: VectorFst<TropicalWeight> = acceptor(&[1, 2, 5], prob(0.3));
+        union(&mut lattice, &fst)?;
+
+        let hypothesis = mbr_decode(&lattice, 1)?;
+        assert_eq!(hypothesis.words, vec![1, 2, 3]);
+        assert_close(hypothesis.expected_errors, 0.3 * 2.0 + 0.3);
+        assert_close(hypothesis.word_risks[0], 0.3);
+        assert_close(hypothesis.word_risks[2], 0.6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mbr_decode_epsilons() -> Result<()> {
+        // `1 2` has a probability of 0.6 and `2` of 0.4.
+        let mut lattice = VectorFst::<TropicalWeight>::new();
+        lattice.add_states(3);
+        lattice.set_start(0)?;
+        lattice.set_final(2, TropicalWeight::one())?;
+        lattice.add_tr(0, Tr::new(1, 1, prob(0.6), 1))?;
+        lattice.add_tr(0, Tr::new(3, EPS_LABEL, prob(0.4), 1))?;
+        lattice.add_tr(1, Tr::new(2, 2, TropicalWeight::one(), 2))?;
+
+        let hypothesis = mbr_decode(&lattice, 10)?;
+        assert_eq!(hypothesis.words, vec![1, 2]);
+        assert_close(hypothesis.expected_errors, 0.4);
+        assert_close(hypothesis.word_risks[0], 0.4);
+        assert_close(hypothesis.word_risks[1], 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mbr_decode_errors() -> Result<()> {
+        let empty = VectorFst::<TropicalWeight>::new();
+        assert!(mbr_decode(&empty, 1).is_err());
+
+        let mut cyclic: VectorFst<TropicalWeight> = acceptor(&[1], TropicalWeight::one());
+        cyclic.add_tr(1, Tr::new(1, 1, TropicalWeight::one(), 0))?;
+        assert!(mbr_decode(&cyclic, 1).is_err());
+        Ok(())
+    }
+}
@@ -33,7 +33,6 @@ use crate::semirings::{
 use crate::Tr;
 use crate::EPS_LABEL;
 use crate::KDELTA;
-use crate::NO_STATE_ID;
 use crate::{StateId, Trs};
 
 /// In place minimization of deterministic weighted automata and transducers,
@@ -242,54 +241,50 @@ impl AcyclicMinimizer {
     }
 
     fn refine<W: Semiring, F: MutableFst<W>>(&mut self, fst: &mut F) {
-        let state_cmp = StateComparator {
-            fst,
-            // This clone is necessary for the moment because the partition is modified while
-            // still needing the StateComparator.
-            // TODO: Find a way to remove the clone.
-            partition: self.partition.clone(),
-            w: PhantomData,
-        };
-
         let height = self.partition.num_classes();
         for h in 0..height {
-            // We need here a binary search tree in order to order the states id and create a partition.
-            // For now uses the crate `stable_bst` which is quite old but seems to do the job
-            // TODO: Bench the performances of the implementation. Maybe re-write it.
-            let mut equiv_classes =
-                TreeMap::<StateId, StateId, _>::with_comparator(|a: &usize, b: &usize| {
-                    state_cmp.compare(*a, *b).unwrap()
-                });
-
             let it_partition: Vec<_> = self.partition.iter(h).collect();
-            equiv_classes.insert(it_partition[0], h);
-
-            let mut classes_to_add = vec![];
-            for e in it_partition.iter().skip(1) {
-                // TODO: Remove double lookup
-                if equiv_classes.contains_key(e) {
-                    equiv_classes.insert(*e, NO_STATE_ID);
-                } else {
-                    classes_to_add.push(e);
-                    equiv_classes.insert(*e, NO_STATE_ID);
-                }
-            }
 
-            for v in classes_to_add {
-                equiv_classes.insert(*v, self.partition.add_class());
-            }
+            // The next states of the states of height h have a smaller height : their classes
+            // are already refined and are not modified while the classes of height h are computed.
+            let representatives: Vec<_> = {
+                let state_cmp = StateComparator {
+                    fst,
+                    partition: &self.partition,
+                    w: PhantomData,
+                };
 
-            for s in it_partition {
-                let old_class = self.partition.get_class_id(s);
-                let new_class = *equiv_classes.get(&s).unwrap();
-                if new_class == NO_STATE_ID {
-                    // The behaviour here is a bit different compared to the c++ because here
-                    // when inserting an equivalent key it modifies the key
-                    // which is not the case in c++.
-                    continue;
-                }
-                if old_class != (new_class as usize) {
-                    self.partition.move_element(s, new_class as usize);
+                // We need here a binary search tree in order to order the states id and create a partition.
+                // For now uses the crate `stable_bst` which is quite old but seems to do the job
+                // TODO: Bench the performances of the implementation. Maybe re-write it.
+                let mut equiv_classes =
+                    TreeMap::<StateId, StateId, _>::with_comparator(|a: &usize, b: &usize| {
+                        state_cmp.compare(*a, *b).unwrap()
+                    });
+
+                it_partition
+                    .iter()
+                    .map(|s| {
+                        // Each state is mapped to the first equivalent state found.
+                        if let Some(representative) = equiv_classes.get(s) {
+                            *representative
+                        } else {
+                            equiv_classes.insert(*s, *s);
+                            *s
+                        }
+                    })
+                    .collect()
+            };
+
+            // The states equivalent to the first one stay in class h.
+            let mut new_classes = HashMap::new();
+            new_classes.insert(it_partition[0], h);
+            for (s, representative) in it_partition.iter().zip(representatives) {
+                let new_class = *new_classes
+                    .entry(representative)
+                    .or_insert_with(|| self.partition.add_class());
+                if new_class != h {
+                    self.partition.move_element(*s, new_class);
                 }
             }
         }
@@ -302,7 +297,7 @@ impl AcyclicMinimizer {
 
 struct StateComparator<'a, W: Semiring, F: MutableFst<W>> {
     fst: &'a F,
-    partition: Partition,
+    partition: &'a Partition,
     w: PhantomData<W>,
 }
 
@@ -519,3 +514,35 @@ impl TrIterCompare {
         xarc.ilabel > yarc.ilabel
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_traits::PathsIterator;
+    use crate::semirings::TropicalWeight;
+
+    #[test]
+    fn test_acyclic_minimize_uses_refined_classes() -> Result<()> {
+        // Accepts `1 3 4` and `2 3 5`. The states reached after `1` and `2` have the same
+        // height and the same labels but their next states end up in different classes.
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(6);
+        fst.set_start(0)?;
+        fst.set_final(5, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::one(), 1))?;
+        fst.add_tr(0, Tr::new(2, 2, TropicalWeight::one(), 2))?;
+        fst.add_tr(1, Tr::new(3, 3, TropicalWeight::one(), 3))?;
+        fst.add_tr(2, Tr::new(3, 3, TropicalWeight::one(), 4))?;
+        fst.add_tr(3, Tr::new(4, 4, TropicalWeight::one(), 5))?;
+        fst.add_tr(4, Tr::new(5, 5, TropicalWeight::one(), 5))?;
+
+        minimize(&mut fst, true)?;
+
+        let mut paths: Vec<_> = fst.paths_iter().map(|p| p.ilabels).collect();
+        paths.sort();
+        assert_eq!(paths, vec![vec![1, 3, 4], vec![2, 3, 5]]);
+        assert_eq!(fst.num_states(), 6);
+        Ok(())
+    }
+}
@@ -1,12 +1,17 @@
 pub use self::{
     add_super_final_state::add_super_final_state,
     all_pairs_shortest_distance::all_pairs_shortest_distance,
+    astar_shortest_path::astar_shortest_path,
+    cdrewrite::{cdrewrite, CdrewriteDirection, CdrewriteMode},
     condense::condense,
+    confusion_network::confusion_network,
     connect::connect,
     fst_convert::{fst_convert, fst_convert_from_ref},
     inversion::invert,
     isomorphic::isomorphic,
+    mbr_decode::{mbr_decode, MbrHypothesis},
     minimize::minimize,
+    posteriors::{posteriors, prune_by_posterior, Posteriors},
     projection::{project, ProjectType},
     push::{push, push_weights, PushType},
     queue::{Queue, QueueType},
@@ -14,8 +19,15 @@ pub use self::{
     reverse::reverse,
     reweight::{reweight, ReweightType},
     rm_final_epsilon::rm_final_epsilon,
-    shortest_distance::shortest_distance,
-    shortest_path::shortest_path,
+    shortest_distance::{
+        shortest_distance, shortest_distance_with_config, ShortestDistanceConfig,
+        ShortestDistanceState,
+    },
+    shortest_path::{
+        shortest_path, shortest_path_trace, shortest_path_with_config, ShortestPathConfig,
+        ShortestPathTrace,
+    },
+    shortest_paths_iterator::{shortest_paths_iter, ShortestPathsIterator},
     state_sort::state_sort,
     top_sort::top_sort,
     tr_map::{tr_map, FinalTr, MapFinalAction, TrMapper},
@@ -27,10 +39,13 @@ pub use self::{
 
 mod add_super_final_state;
 mod all_pairs_shortest_distance;
+mod astar_shortest_path;
+mod cdrewrite;
 pub mod closure;
 pub mod compose;
 pub mod concat;
 mod condense;
+mod confusion_network;
 mod connect;
 pub mod determinize;
 pub(crate) mod dfs_visit;
@@ -39,8 +54,10 @@ pub mod factor_weight;
 mod fst_convert;
 mod inversion;
 mod isomorphic;
+mod mbr_decode;
 mod minimize;
 mod partition;
+mod posteriors;
 mod projection;
 mod push;
 mod queue;
@@ -52,6 +69,7 @@ pub mod rm_epsilon;
 mod rm_final_epsilon;
 mod shortest_distance;
 mod shortest_path;
+mod shortest_paths_iterator;
 mod state_sort;
 mod top_sort;
 mod tr_map;
@@ -104,7 +104,7 @@ impl Partition {
         }
 
         if this_class.yes_head >= 0 {
-            self.elements[this_class.yes_head as usize].prev_element = elt_prev_elt;
+            self.elements[this_class.yes_head as usize].prev_element = element_id as i32;
         } else {
             self.visited_classes.push(elt_class_id);
         }
@@ -262,3 +262,30 @@ impl<'a> Iterator for PartitionIterator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::queues::LifoQueue;
+
+    #[test]
+    fn test_partition_split_on_keeps_links() {
+        let mut partition = Partition::new(3);
+        partition.allocate_classes(1);
+        for e in 0..3 {
+            partition.add(e, 0);
+        }
+        partition.split_on(1);
+        partition.split_on(0);
+        partition.finalize_split::<LifoQueue>(&mut None);
+        assert_eq!(partition.num_classes(), 2);
+        assert_eq!(partition.iter(1).collect::<Vec<_>>(), vec![2]);
+
+        let new_class = partition.add_class();
+        partition.move_element(1, new_class);
+        assert_eq!(partition.iter(0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(partition.iter(1).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(partition.iter(new_class).collect::<Vec<_>>(), vec![1]);
+    }
+}
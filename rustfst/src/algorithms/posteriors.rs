@@ -0,0 +1,244 @@
+use anyhow::{bail, Result};
+use num_traits::AsPrimitive;
+
+use crate::algorithms::weight_converters::FloatWeightConverter;
+use crate::algorithms::{connect, shortest_distance, weight_convert};
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+use crate::semirings::{LogWeight, Semiring};
+use crate::trs::Trs;
+
+/// Posterior probabilities of the states and trs of an FST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posteriors {
+    /// Probability that a path goes through each state, indexed by state id.
+    pub states: Vec<f32>,
+    /// Probability that a path goes through each tr, indexed by state id and tr index.
+    pub trs: Vec<Vec<f32>>,
+    /// Probability that a path ends in each state, indexed by state id.
+    pub finals: Vec<f32>,
+    /// Sum of the weights of all the paths, in the log semiring.
+    pub total_weight: LogWeight,
+}
+
+/// Computes the posterior probabilities of the states and trs of an FST with the
+/// forward-backward algorithm.
+///
+/// The weights are interpreted as negative log probabilities and converted to the log
+/// semiring. The forward and backward weights are computed with `shortest_distance` and the
+/// posterior of a tr going from `p` to `q` is `alpha[p] * w * beta[q] / beta[start]`.
+///
+/// Returns an error if the FST has no successful path.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::posteriors;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<TropicalWeight>::new();
+/// fst.add_states(2);
+/// fst.set_start(0)?;
+/// fst.set_final(1, TropicalWeight::one())?;
+/// fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(0.25f32.ln().abs()), 1))?;
+/// fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(0.75f32.ln().abs()), 1))?;
+///
+/// let posteriors = posteriors(&fst)?;
+/// assert!((posteriors.trs[0][0] - 0.25).abs() < 1e-5);
+/// assert!((posteriors.trs[0][1] - 0.75).abs() < 1e-5);
+/// assert!((posteriors.states[1] - 1.0).abs() < 1e-5);
+/// # Ok(())
+/// # }
+/// ```
+pub fn posteriors<W, F>(fst: &F) -> Result<Posteriors>
+where
+    W: Semiring,
+    W::Type: AsPrimitive<f32>,
+    F: ExpandedFst<W>,
+{
+    let log_fst: VectorFst<LogWeight> = weight_convert(fst, &mut FloatWeightConverter {})?;
+    let num_states = log_fst.num_states();
+
+    let mut alpha = shortest_distance(&log_fst, false)?;
+    alpha.resize(num_states, LogWeight::zero());
+    let mut beta = shortest_distance(&log_fst, true)?;
+    beta.resize(num_states, LogWeight::zero());
+
+    let total_weight = match log_fst.start() {
+        Some(start) => beta[start],
+        None => LogWeight::zero(),
+    };
+    if total_weight == LogWeight::zero() {
+        bail!("posteriors : the FST has no successful path");
+    }
+
+    let posterior = |weights: &[&LogWeight]| -> f32 {
+        let log_prob: f32 = weights.iter().map(|w| *w.value()).sum::<f32>() - total_weight.value();
+        (-log_prob).exp()
+    };
+
+    let mut states = Vec::with_capacity(num_states);
+    let mut trs = Vec::with_capacity(num_states);
+    let mut finals = Vec::with_capacity(num_states);
+    for s in 0..num_states {
+        states.push(posterior(&[&alpha[s], &beta[s]]));
+        trs.push(
+            log_fst
+                .get_trs(s)?
+                .trs()
+                .iter()
+                .map(|tr| posterior(&[&alpha[s], &tr.weight, &beta[tr.nextstate]]))
+                .collect(),
+        );
+        finals.push(match log_fst.final_weight(s)? {
+            Some(final_weight) => posterior(&[&alpha[s], &final_weight]),
+            None => 0.0,
+        });
+    }
+
+    Ok(Posteriors {
+        states,
+        trs,
+        finals,
+        total_weight,
+    })
+}
+
+/// Removes the trs whose posterior probability is below `threshold`, then the states that
+/// are no longer accessible or coaccessible.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::prune_by_posterior;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+/// # use rustfst::semirings::{LogWeight, Semiring};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<LogWeight>::new();
+/// fst.add_states(3);
+/// fst.set_start(0)?;
+/// fst.set_final(2, LogWeight::one())?;
+/// fst.add_tr(0, Tr::new(1, 1, LogWeight::new(0.01f32.ln().abs()), 1))?;
+/// fst.add_tr(0, Tr::new(2, 2, LogWeight::new(0.99f32.ln().abs()), 2))?;
+/// fst.add_tr(1, Tr::new(3, 3, LogWeight::one(), 2))?;
+///
+/// prune_by_posterior(&mut fst, 0.05)?;
+/// assert_eq!(fst.num_states(), 2);
+/// assert_eq!(fst.num_trs(0)?, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub fn prune_by_posterior<W, F>(fst: &mut F, threshold: f32) -> Result<()>
+where
+    W: Semiring,
+    W::Type: AsPrimitive<f32>,
+    F: MutableFst<W> + ExpandedFst<W>,
+{
+    let posteriors = posteriors(fst)?;
+    for (s, tr_posteriors) in posteriors.trs.iter().enumerate() {
+        if tr_posteriors.iter().all(|p| *p >= threshold) {
+            continue;
+        }
+        let trs = fst.pop_trs(s)?;
+        for (tr, p) in trs.into_iter().zip(tr_posteriors) {
+            if *p >= threshold {
+                fst.add_tr(s, tr)?;
+            }
+        }
+    }
+    connect(fst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::semirings::TropicalWeight;
+    use crate::Tr;
+
+    fn prob(p: f32) -> TropicalWeight {
+        TropicalWeight::new(-p.ln())
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_posteriors_diamond() -> Result<()> {
+        // Paths 1 3 (0.2 * 0.5), 1 4 (0.2 * 0.5) and 2 3 (0.6), with a final weight of 0.5.
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(4);
+        fst.set_start(0)?;
+        fst.set_final(3, prob(0.5))?;
+        fst.add_tr(0, Tr::new(1, 1, prob(0.2), 1))?;
+        fst.add_tr(0, Tr::new(2, 2, prob(0.6), 2))?;
+        fst.add_tr(1, Tr::new(3, 3, prob(0.5), 3))?;
+        fst.add_tr(1, Tr::new(4, 4, prob(0.5), 3))?;
+        fst.add_tr(2, Tr::new(3, 3, prob(1.0), 3))?;
+
+        let posteriors = posteriors(&fst)?;
+        assert_close(*posteriors.total_weight.value(), -(0.4f32).ln());
+        let expected_states = [1.0, 0.25, 0.75, 1.0];
+        for (p, e) in posteriors.states.iter().zip(expected_states.iter()) {
+            assert_close(*p, *e);
+        }
+        let expected_trs = [vec![0.25, 0.75], vec![0.125, 0.125], vec![0.75], vec![]];
+        for (ps, es) in posteriors.trs.iter().zip(expected_trs.iter()) {
+            assert_eq!(ps.len(), es.len());
+            for (p, e) in ps.iter().zip(es.iter()) {
+                assert_close(*p, *e);
+            }
+        }
+        assert_close(posteriors.finals[3], 1.0);
+        assert_close(posteriors.finals[0], 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_posteriors_unreachable_states() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(3);
+        fst.set_start(0)?;
+        fst.set_final(1, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::one(), 1))?;
+        fst.add_tr(2, Tr::new(1, 1, TropicalWeight::one(), 1))?;
+
+        let posteriors = posteriors(&fst)?;
+        assert_close(posteriors.states[2], 0.0);
+        assert_close(posteriors.trs[2][0], 0.0);
+
+        let empty = VectorFst::<TropicalWeight>::new();
+        assert!(super::posteriors(&empty).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_by_posterior() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(3);
+        fst.set_start(0)?;
+        fst.set_final(2, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, prob(0.1), 1))?;
+        fst.add_tr(0, Tr::new(2, 2, prob(0.9), 2))?;
+        fst.add_tr(1, Tr::new(3, 3, prob(0.5), 2))?;
+        fst.add_tr(1, Tr::new(4, 4, prob(0.5), 2))?;
+
+        let mut pruned = fst.clone();
+        prune_by_posterior(&mut pruned, 0.01)?;
+        assert_eq!(pruned, fst);
+
+        // Both trs leaving state 1 have a posterior of 0.1 * 0.5.
+        prune_by_posterior(&mut pruned, 0.06)?;
+        assert_eq!(pruned.num_states(), 2);
+        assert_eq!(pruned.num_trs(0)?, 1);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use crate::fst_traits::{AllocableFst, ExpandedFst, MutableFst};
+use crate::fst_traits::{AllocableFst, Fst, MutableFst};
 use crate::semirings::Semiring;
 use crate::tr::Tr;
 use crate::{Trs, EPS_LABEL};
@@ -29,36 +29,38 @@ use crate::{Trs, EPS_LABEL};
 pub fn reverse<W, F1, F2>(ifst: &F1) -> Result<F2>
 where
     W: Semiring,
-    F1: ExpandedFst<W>,
+    F1: Fst<W>,
     F2: MutableFst<W::ReverseWeight> + AllocableFst<W::ReverseWeight>,
 {
+    // Iterating over the states of a lazy FST computes all of them.
+    let num_states = ifst.states_iter().count();
     let mut ofst = F2::new();
-    ofst.reserve_states(ifst.num_states());
+    ofst.reserve_states(num_states);
     let istart = ifst.start();
     let ostart = ofst.add_state();
 
-    ofst.add_states(ifst.num_states());
+    ofst.add_states(num_states);
 
-    let mut c_trs = vec![0; ifst.num_states() + 1];
-    for is in 0..ifst.num_states() {
-        for iarc in unsafe { ifst.get_trs_unchecked(is).trs() } {
+    let mut c_trs = vec![0; num_states + 1];
+    for is in 0..num_states {
+        for iarc in ifst.get_trs(is)?.trs() {
             c_trs[iarc.nextstate + 1] += 1;
         }
     }
 
     let mut states_trs: Vec<_> = c_trs.into_iter().map(Vec::with_capacity).collect();
 
-    for is in 0..ifst.num_states() {
+    for is in 0..num_states {
         let os = is + 1;
         if Some(is) == istart {
             ofst.set_final(os, W::ReverseWeight::one())?;
         }
-        let weight = unsafe { ifst.final_weight_unchecked(is) };
+        let weight = ifst.final_weight(is)?;
         if let Some(w) = weight {
             states_trs[0].push(Tr::new(EPS_LABEL, EPS_LABEL, w.reverse()?, os));
         }
 
-        for iarc in unsafe { ifst.get_trs_unchecked(is).trs() } {
+        for iarc in ifst.get_trs(is)?.trs() {
             let nos = iarc.nextstate + 1;
             let weight = iarc.weight.reverse()?;
             let w = Tr::new(iarc.ilabel, iarc.olabel, weight, os);
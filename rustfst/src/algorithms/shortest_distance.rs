@@ -2,11 +2,11 @@ use std::marker::PhantomData;
 
 use anyhow::Result;
 
-use crate::algorithms::queues::AutoQueue;
+use crate::algorithms::queues::{AutoQueue, FifoQueue};
 use crate::algorithms::tr_filters::{AnyTrFilter, TrFilter};
 use crate::algorithms::Queue;
 use crate::fst_impls::VectorFst;
-use crate::fst_traits::{ExpandedFst, MutableFst};
+use crate::fst_traits::{Fst, MutableFst};
 use crate::semirings::{ReverseBack, Semiring, SemiringProperties};
 use crate::{StateId, Trs};
 use std::borrow::Borrow;
@@ -37,13 +37,7 @@ impl<W: Semiring, Q: Queue, A: TrFilter<W>> ShortestDistanceConfig<W, Q, A> {
 }
 
 #[derive(Clone, Eq)]
-pub struct ShortestDistanceState<
-    W: Semiring,
-    Q: Queue,
-    F: ExpandedFst<W>,
-    B: Borrow<F>,
-    A: TrFilter<W>,
-> {
+pub struct ShortestDistanceState<W: Semiring, Q: Queue, F: Fst<W>, B: Borrow<F>, A: TrFilter<W>> {
     pub fst: B,
     state_queue: Q,
     tr_filter: A,
@@ -58,8 +52,8 @@ pub struct ShortestDistanceState<
     f: PhantomData<F>,
 }
 
-impl<W: Semiring, Q: Queue + PartialEq, F: ExpandedFst<W>, B: Borrow<F>, A: TrFilter<W>> PartialEq
-    for ShortestDistanceState<W, Q, F, B, A>
+impl<W: Semiring, Q: Queue + PartialEq, F: Fst<W> + PartialEq, B: Borrow<F>, A: TrFilter<W>>
+    PartialEq for ShortestDistanceState<W, Q, F, B, A>
 {
     fn eq(&self, other: &Self) -> bool {
         self.fst.borrow().eq(&other.fst.borrow())
@@ -76,7 +70,7 @@ impl<W: Semiring, Q: Queue + PartialEq, F: ExpandedFst<W>, B: Borrow<F>, A: TrFi
     }
 }
 
-impl<W: Semiring, Q: Queue, F: ExpandedFst<W>, B: Borrow<F>, A: TrFilter<W>> std::fmt::Debug
+impl<W: Semiring, Q: Queue, F: Fst<W>, B: Borrow<F>, A: TrFilter<W>> std::fmt::Debug
     for ShortestDistanceState<W, Q, F, B, A>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -116,7 +110,7 @@ macro_rules! ensure_source_index_is_valid {
     };
 }
 
-impl<W: Semiring, Q: Queue, F: ExpandedFst<W>, B: Borrow<F>, A: TrFilter<W>>
+impl<W: Semiring, Q: Queue, F: Fst<W>, B: Borrow<F>, A: TrFilter<W>>
     ShortestDistanceState<W, Q, F, B, A>
 {
     pub fn new(fst: B, state_queue: Q, tr_filter: A, first_path: bool, retain: bool) -> Self {
@@ -124,11 +118,11 @@ impl<W: Semiring, Q: Queue, F: ExpandedFst<W>, B: Borrow<F>, A: TrFilter<W>>
             state_queue,
             tr_filter,
             first_path,
-            distance: Vec::with_capacity(fst.borrow().num_states()),
-            enqueued: Vec::with_capacity(fst.borrow().num_states()),
-            adder: Vec::with_capacity(fst.borrow().num_states()),
-            radder: Vec::with_capacity(fst.borrow().num_states()),
-            sources: Vec::with_capacity(fst.borrow().num_states()),
+            distance: vec![],
+            enqueued: vec![],
+            adder: vec![],
+            radder: vec![],
+            sources: vec![],
             source_id: 0,
             retain,
             fst,
@@ -243,7 +237,47 @@ impl<W: Semiring, Q: Queue, F: ExpandedFst<W>, B: Borrow<F>, A: TrFilter<W>>
     }
 }
 
-pub fn shortest_distance_with_config<W: Semiring, Q: Queue, A: TrFilter<W>, F: ExpandedFst<W>>(
+/// Computes the shortest distance from the source state, or the initial state, to every state
+/// with the queue and tr filter of the config.
+///
+/// Contrary to `shortest_distance`, the FST doesn't need to be expanded : the states are
+/// visited as they are discovered and the distances are stored in vectors growing with the
+/// largest state id reached. A lazy FST like a `ComposeFst` can be used directly, only its
+/// states reachable from the source are computed. The queue must not require the FST to be
+/// expanded, e.g a `FifoQueue` or a `LifoQueue`.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::compose::ComposeFst;
+/// # use rustfst::algorithms::queues::FifoQueue;
+/// # use rustfst::algorithms::tr_filters::AnyTrFilter;
+/// # use rustfst::algorithms::{shortest_distance_with_config, ShortestDistanceConfig};
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst1 = VectorFst::<TropicalWeight>::new();
+/// fst1.add_states(2);
+/// fst1.set_start(0)?;
+/// fst1.set_final(1, TropicalWeight::one())?;
+/// fst1.add_tr(0, Tr::new(1, 2, TropicalWeight::new(1.0), 1))?;
+/// let mut fst2 = VectorFst::<TropicalWeight>::new();
+/// fst2.add_states(2);
+/// fst2.set_start(0)?;
+/// fst2.set_final(1, TropicalWeight::one())?;
+/// fst2.add_tr(0, Tr::new(2, 3, TropicalWeight::new(2.0), 1))?;
+///
+/// let compose_fst = ComposeFst::new_auto(Arc::new(fst1), Arc::new(fst2))?;
+/// let config = ShortestDistanceConfig::new_with_default(AnyTrFilter {}, FifoQueue::default());
+/// let distance = shortest_distance_with_config(&compose_fst, config)?;
+/// assert_eq!(distance, vec![TropicalWeight::one(), TropicalWeight::new(3.0)]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn shortest_distance_with_config<W: Semiring, Q: Queue, A: TrFilter<W>, F: Fst<W>>(
     fst: &F,
     opts: ShortestDistanceConfig<W, Q, A>,
 ) -> Result<Vec<W>> {
@@ -256,6 +290,11 @@ pub fn shortest_distance_with_config<W: Semiring, Q: Queue, A: TrFilter<W>, F: E
 /// The shortest distance from `p` to `q` is the ⊕-sum of the weights
 /// of all the paths between `p` and `q`.
 ///
+/// The FST doesn't need to be expanded. The forward distances are computed with a
+/// `FifoQueue`, visiting only the states reachable from the initial state. The reverse
+/// distances are computed on the reversed FST, which expands all the states, with a queue
+/// chosen from its properties.
+///
 /// # Example
 /// ```
 /// # use rustfst::semirings::{Semiring, IntegerWeight};
@@ -285,11 +324,9 @@ pub fn shortest_distance_with_config<W: Semiring, Q: Queue, A: TrFilter<W>, F: E
 /// # Ok(())
 /// # }
 /// ```
-pub fn shortest_distance<W: Semiring, F: ExpandedFst<W>>(fst: &F, reverse: bool) -> Result<Vec<W>> {
+pub fn shortest_distance<W: Semiring, F: Fst<W>>(fst: &F, reverse: bool) -> Result<Vec<W>> {
     if !reverse {
-        let tr_filter = AnyTrFilter {};
-        let queue = AutoQueue::new(fst, None, &tr_filter)?;
-        let config = ShortestDistanceConfig::new_with_default(tr_filter, queue);
+        let config = ShortestDistanceConfig::new_with_default(AnyTrFilter {}, FifoQueue::default());
         shortest_distance_with_config(fst, config)
     } else {
         let tr_filter = AnyTrFilter {};
@@ -333,3 +370,47 @@ fn shortest_distance_3<W: Semiring, F: MutableFst<W>>(fst: &F) -> Result<W> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::algorithms::compose::{compose, ComposeFst};
+    use crate::semirings::LogWeight;
+    use crate::Tr;
+
+    #[test]
+    fn test_shortest_distance_lazy_fst() -> Result<()> {
+        let mut fst1 = VectorFst::<LogWeight>::new();
+        fst1.add_states(3);
+        fst1.set_start(0)?;
+        fst1.set_final(2, LogWeight::new(0.5))?;
+        fst1.add_tr(0, Tr::new(1, 1, LogWeight::new(1.0), 1))?;
+        fst1.add_tr(0, Tr::new(2, 2, LogWeight::new(2.0), 1))?;
+        fst1.add_tr(1, Tr::new(3, 3, LogWeight::new(0.5), 2))?;
+        fst1.add_tr(1, Tr::new(1, 1, LogWeight::new(3.0), 0))?;
+        let mut fst2 = VectorFst::<LogWeight>::new();
+        fst2.add_states(1);
+        fst2.set_start(0)?;
+        fst2.set_final(0, LogWeight::one())?;
+        for label in 1..4 {
+            fst2.add_tr(0, Tr::new(label, label, LogWeight::new(0.25), 0))?;
+        }
+        let fst1 = Arc::new(fst1);
+        let fst2 = Arc::new(fst2);
+
+        let composed: VectorFst<LogWeight> = compose(fst1.clone(), fst2.clone())?;
+        for reverse in &[false, true] {
+            let lazy_fst = ComposeFst::new_auto(fst1.clone(), fst2.clone())?;
+            let distance = shortest_distance(&lazy_fst, *reverse)?;
+            let expected = shortest_distance(&composed, *reverse)?;
+            assert_eq!(distance.len(), expected.len());
+            for (w1, w2) in distance.iter().zip(expected.iter()) {
+                assert!((w1.value() - w2.value()).abs() < 1e-4);
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,10 +6,12 @@ use unsafe_unwrap::UnsafeUnwrap;
 
 use crate::algorithms::determinize::determinize_with_distance;
 use crate::algorithms::queues::AutoQueue;
+use crate::algorithms::shortest_distance::{shortest_distance_with_config, ShortestDistanceConfig};
 use crate::algorithms::tr_filters::AnyTrFilter;
-use crate::algorithms::{connect, reverse, shortest_distance, Queue};
+use crate::algorithms::{connect, reverse, Queue};
 use crate::fst_impls::VectorFst;
-use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+use crate::fst_path::FstPath;
+use crate::fst_traits::{CoreFst, ExpandedFst, Fst, MutableFst};
 use crate::semirings::{
     ReverseBack, Semiring, SemiringProperties, WeaklyDivisibleSemiring, WeightQuantize,
 };
@@ -18,6 +20,62 @@ use crate::{Tr, KDELTA};
 use bitflags::_core::fmt::Formatter;
 use std::fmt::Debug;
 
+/// Configuration of the [`shortest_path_with_config`](fn.shortest_path_with_config.html)
+/// algorithm.
+pub struct ShortestPathConfig<W: Semiring, Q: Queue> {
+    /// Queue used to visit the states of the FST.
+    pub state_queue: Q,
+    /// Number of paths to return.
+    pub nshortest: usize,
+    /// Only return paths with distinct input and output labels.
+    pub unique: bool,
+    /// Stop the search as soon as a final state is dequeued. Only supported with
+    /// `nshortest == 1`, the path returned is the shortest one if the queue is a
+    /// shortest-first queue.
+    pub first_path: bool,
+    /// Prune the paths whose weight is greater than the weight of the shortest path times
+    /// this threshold. `W::zero()` disables the pruning.
+    pub weight_threshold: W,
+    /// Stop adding states once the search has reached this number of states.
+    pub state_threshold: Option<StateId>,
+    /// Comparison threshold used to order the candidate paths.
+    pub delta: f32,
+}
+
+impl<W: Semiring, Q: Queue> ShortestPathConfig<W, Q> {
+    pub fn new(
+        state_queue: Q,
+        nshortest: usize,
+        unique: bool,
+        first_path: bool,
+        weight_threshold: W,
+        state_threshold: Option<StateId>,
+        delta: f32,
+    ) -> Self {
+        Self {
+            state_queue,
+            nshortest,
+            unique,
+            first_path,
+            weight_threshold,
+            state_threshold,
+            delta,
+        }
+    }
+
+    pub fn new_with_default(state_queue: Q, nshortest: usize, unique: bool) -> Self {
+        Self::new(
+            state_queue,
+            nshortest,
+            unique,
+            false,
+            W::zero(),
+            None,
+            KDELTA,
+        )
+    }
+}
+
 /// Creates an FST containing the n-shortest paths in the input FST. The n-shortest paths are the
 /// n-lowest weight paths w.r.t. the natural semiring order.
 ///
@@ -45,6 +103,78 @@ where
         + From<<W as Semiring>::ReverseWeight>,
     <W as Semiring>::ReverseWeight: WeightQuantize + WeaklyDivisibleSemiring,
 {
+    let queue = AutoQueue::new(ifst, None, &AnyTrFilter {})?;
+    let config = ShortestPathConfig::new_with_default(queue, nshortest, unique);
+    shortest_path_with_config(ifst, config)
+}
+
+/// Same as [`shortest_path`](fn.shortest_path.html) but with a configurable queue and
+/// pruning. On large FSTs, the weight and state thresholds limit the part of the FST explored
+/// by the search, like a beam.
+///
+/// The FST doesn't need to be expanded : with `nshortest == 1` and a queue that doesn't
+/// require an expanded FST, e.g a `FifoQueue`, only the states reached by the search are
+/// computed on a lazy FST. Computing more than one path requires to reverse the FST, which
+/// visits all its states.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::queues::FifoQueue;
+/// # use rustfst::algorithms::{shortest_path_with_config, ShortestPathConfig};
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::{MutableFst, PathsIterator};
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::{Tr, KDELTA};
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<TropicalWeight>::new();
+/// fst.add_states(2);
+/// fst.set_start(0)?;
+/// fst.set_final(1, TropicalWeight::one())?;
+/// fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+/// fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(1.5), 1))?;
+/// fst.add_tr(0, Tr::new(3, 3, TropicalWeight::new(5.0), 1))?;
+///
+/// // Keeps at most 3 paths, within a weight of 2.0 of the shortest one.
+/// let config = ShortestPathConfig::new(
+///     FifoQueue::default(),
+///     3,
+///     false,
+///     false,
+///     TropicalWeight::new(2.0),
+///     None,
+///     KDELTA,
+/// );
+/// let paths: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+/// assert_eq!(paths.paths_iter().count(), 2);
+/// # Ok(())
+/// # }
+/// ```
+pub fn shortest_path_with_config<W, FI, FO, Q>(
+    ifst: &FI,
+    config: ShortestPathConfig<W, Q>,
+) -> Result<FO>
+where
+    FI: Fst<W>,
+    FO: MutableFst<W>,
+    Q: Queue,
+    W: Semiring
+        + WeightQuantize
+        + Into<<W as Semiring>::ReverseWeight>
+        + From<<W as Semiring>::ReverseWeight>,
+    <W as Semiring>::ReverseWeight: WeightQuantize + WeaklyDivisibleSemiring,
+{
+    let ShortestPathConfig {
+        mut state_queue,
+        nshortest,
+        unique,
+        first_path,
+        weight_threshold,
+        state_threshold,
+        delta,
+    } = config;
+
     if nshortest == 0 {
         return Ok(FO::new());
     }
@@ -54,17 +184,31 @@ where
         let mut f_parent = None;
         let mut distance = vec![];
 
-        single_shortest_path(ifst, &mut distance, &mut f_parent, &mut parent)?;
+        single_shortest_path(
+            ifst,
+            &mut distance,
+            &mut f_parent,
+            &mut parent,
+            &mut state_queue,
+            first_path,
+            &weight_threshold,
+            state_threshold,
+        )?;
         let mut fst_res: FO = single_shortest_path_backtrace(ifst, &f_parent, &parent)?;
         fst_res.set_symts_from_fst(ifst);
         return Ok(fst_res);
     }
 
+    if first_path {
+        bail!("ShortestPath : The first_path option is only supported with nshortest == 1")
+    }
+
     if !W::properties().contains(SemiringProperties::PATH | SemiringProperties::SEMIRING) {
         bail!("ShortestPath : Weight need to have the Path property and be distributive")
     }
 
-    let mut distance = shortest_distance(ifst, false)?;
+    let sd_config = ShortestDistanceConfig::new_with_default(AnyTrFilter {}, state_queue);
+    let mut distance = shortest_distance_with_config(ifst, sd_config)?;
 
     let rfst: VectorFst<_> = reverse(ifst)?;
     let mut d = W::zero();
@@ -78,8 +222,14 @@ where
 
     let mut distance_2 = vec![d];
     distance_2.append(&mut distance);
+    let n_shortest_config = NShortestPathConfig {
+        nshortest,
+        weight_threshold,
+        state_threshold,
+        delta,
+    };
     let mut fst_res: FO = if !unique {
-        n_shortest_path(&rfst, &distance_2, nshortest)?
+        n_shortest_path(&rfst, &distance_2, n_shortest_config)?
     } else {
         let distance_2_reversed: Vec<<W as Semiring>::ReverseWeight> =
             distance_2.into_iter().map(|v| v.into()).collect();
@@ -89,7 +239,7 @@ where
             .into_iter()
             .map(|v| v.reverse_back())
             .collect::<Result<Vec<_>>>()?;
-        n_shortest_path(&dfst, &distance_3, nshortest)?
+        n_shortest_path(&dfst, &distance_3, n_shortest_config)?
     };
 
     fst_res.set_symts_from_fst(ifst);
@@ -97,15 +247,124 @@ where
     Ok(fst_res)
 }
 
-fn single_shortest_path<W, F>(
+/// Shortest path of an FST along with the states and trs of the FST it goes through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortestPathTrace<W: Semiring> {
+    /// Labels and weight of the path.
+    pub path: FstPath<W>,
+    /// States of the FST along the path, from the start state to the final state.
+    pub states: Vec<StateId>,
+    /// Index of the tr taken in each state of `states` but the last one, in the trs of the
+    /// state.
+    pub tr_indices: Vec<usize>,
+}
+
+/// Computes the shortest path of an FST and traces it back in the FST : contrary to
+/// `shortest_path`, which creates a new FST, the ids of the states and the indices of the trs
+/// of the path are returned, e.g to align the path with HMM states.
+///
+/// Returns `None` if the FST has no successful path.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::shortest_path_trace;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<TropicalWeight>::new();
+/// fst.add_states(3);
+/// fst.set_start(0)?;
+/// fst.set_final(2, TropicalWeight::new(0.5))?;
+/// fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(2.0), 2))?;
+/// fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(1.0), 1))?;
+/// fst.add_tr(1, Tr::new(3, 3, TropicalWeight::new(0.5), 2))?;
+///
+/// let trace = shortest_path_trace(&fst)?.unwrap();
+/// assert_eq!(trace.states, vec![0, 1, 2]);
+/// assert_eq!(trace.tr_indices, vec![1, 0]);
+/// assert_eq!(trace.path.ilabels, vec![2, 3]);
+/// assert_eq!(trace.path.weight, TropicalWeight::new(2.0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn shortest_path_trace<W, F>(fst: &F) -> Result<Option<ShortestPathTrace<W>>>
+where
+    W: Semiring,
+    F: ExpandedFst<W>,
+{
+    let mut parent = vec![];
+    let mut f_parent = None;
+    let mut distance = vec![];
+    let mut queue = AutoQueue::new(fst, None, &AnyTrFilter {})?;
+    single_shortest_path(
+        fst,
+        &mut distance,
+        &mut f_parent,
+        &mut parent,
+        &mut queue,
+        false,
+        &W::zero(),
+        None,
+    )?;
+
+    match f_parent {
+        Some(final_state) => Ok(Some(trace_path(fst, &parent, final_state)?)),
+        None => Ok(None),
+    }
+}
+
+/// Builds the trace of the path ending in `final_state` by following the parents of the
+/// states, i.e the previous state and the index of the tr taken from it.
+pub(crate) fn trace_path<W: Semiring, F: CoreFst<W>>(
+    fst: &F,
+    parent: &[Option<(StateId, usize)>],
+    final_state: StateId,
+) -> Result<ShortestPathTrace<W>> {
+    let mut states = vec![final_state];
+    let mut tr_indices = vec![];
+    while let Some((state, pos)) = parent[*states.last().unwrap()] {
+        states.push(state);
+        tr_indices.push(pos);
+    }
+    states.reverse();
+    tr_indices.reverse();
+
+    let mut path = FstPath::default();
+    for (state, pos) in states.iter().zip(tr_indices.iter()) {
+        let trs = fst.get_trs(*state)?;
+        let tr = &trs.trs()[*pos];
+        path.add_to_path(tr.ilabel, tr.olabel, &tr.weight)?;
+    }
+    if let Some(final_weight) = fst.final_weight(final_state)? {
+        path.add_weight(&final_weight)?;
+    }
+
+    Ok(ShortestPathTrace {
+        path,
+        states,
+        tr_indices,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn single_shortest_path<W, F, Q>(
     ifst: &F,
     distance: &mut Vec<W>,
     f_parent: &mut Option<StateId>,
     parent: &mut Vec<Option<(StateId, usize)>>,
+    queue: &mut Q,
+    first_path: bool,
+    weight_threshold: &W,
+    state_threshold: Option<StateId>,
 ) -> Result<()>
 where
     W: Semiring,
-    F: ExpandedFst<W>,
+    F: Fst<W>,
+    Q: Queue,
 {
     parent.clear();
     *f_parent = None;
@@ -114,7 +373,6 @@ where
         return Ok(());
     }
     let mut enqueued = vec![];
-    let mut queue = AutoQueue::new(ifst, None, &AnyTrFilter {})?;
     let source = unsafe { start.unsafe_unwrap() };
     let mut f_distance = W::zero();
     distance.clear();
@@ -124,13 +382,15 @@ where
             "SingleShortestPath: Weight needs to have the path property and be right distributive"
         )
     }
-    distance.resize_with(ifst.num_states(), W::zero);
-    enqueued.resize(ifst.num_states(), false);
-    parent.resize(ifst.num_states(), None);
+    // The FST may not be expanded, the vectors grow as the states are discovered.
+    distance.resize_with(source + 1, W::zero);
+    enqueued.resize(source + 1, false);
+    parent.resize(source + 1, None);
 
     distance[source] = W::one();
     parent[source] = None;
     enqueued[source] = true;
+    let mut num_visited = 1;
 
     queue.enqueue(source);
 
@@ -141,7 +401,12 @@ where
         enqueued[s] = false;
         let sd = distance[s].clone();
 
-        if let Some(final_weight) = unsafe { ifst.final_weight_unchecked(s) } {
+        // With a shortest-first queue, no other path can be shorter than f_distance.
+        if first_path && f_parent.is_some() {
+            break;
+        }
+
+        if let Some(final_weight) = ifst.final_weight(s)? {
             let plus = f_distance.plus(&sd.times(final_weight)?)?;
             if f_distance != plus {
                 f_distance = plus;
@@ -149,9 +414,24 @@ where
             }
         }
 
-        for (pos, tr) in unsafe { ifst.get_trs_unchecked(s).trs().iter().enumerate() } {
-            let nd = &mut distance[tr.nextstate];
+        let limit = f_distance.times(weight_threshold)?;
+        for (pos, tr) in ifst.get_trs(s)?.trs().iter().enumerate() {
             let weight = sd.times(&tr.weight)?;
+            if f_parent.is_some() && natural_less(&limit, &weight)? {
+                continue;
+            }
+            if distance.len() <= tr.nextstate {
+                distance.resize_with(tr.nextstate + 1, W::zero);
+                enqueued.resize(tr.nextstate + 1, false);
+                parent.resize(tr.nextstate + 1, None);
+            }
+            let nd = &mut distance[tr.nextstate];
+            if nd.is_zero() {
+                if matches!(state_threshold, Some(t) if num_visited >= t) {
+                    continue;
+                }
+                num_visited += 1;
+            }
             if *nd != nd.plus(&weight)? {
                 *nd = nd.plus(&weight)?;
                 parent[tr.nextstate] = Some((s, pos));
@@ -174,7 +454,7 @@ fn single_shortest_path_backtrace<W, FI, FO>(
 ) -> Result<FO>
 where
     W: Semiring,
-    FI: ExpandedFst<W>,
+    FI: Fst<W>,
     FO: MutableFst<W>,
 {
     let mut ofst = FO::new();
@@ -215,15 +495,21 @@ pub fn natural_less<W: Semiring>(w1: &W, w2: &W) -> Result<bool> {
 struct ShortestPathCompare<'a, 'b, W: Semiring> {
     pairs: &'a RefCell<Vec<(Option<StateId>, W)>>,
     distance: &'b [W],
+    delta: f32,
     weight_zero: W,
     weight_one: W,
 }
 
 impl<'a, 'b, W: Semiring + WeightQuantize> ShortestPathCompare<'a, 'b, W> {
-    pub fn new(pairs: &'a RefCell<Vec<(Option<StateId>, W)>>, distance: &'b [W]) -> Self {
+    pub fn new(
+        pairs: &'a RefCell<Vec<(Option<StateId>, W)>>,
+        distance: &'b [W],
+        delta: f32,
+    ) -> Self {
         Self {
             pairs,
             distance,
+            delta,
             weight_zero: W::zero(),
             weight_one: W::one(),
         }
@@ -249,10 +535,10 @@ impl<'a, 'b, W: Semiring + WeightQuantize> ShortestPathCompare<'a, 'b, W> {
         let wy = self.pweight(&py.0).times(&py.1).unwrap();
         let res = if px.0.is_none() && py.0.is_some() {
             natural_less(&wy, &wx).unwrap()
-                || (wy.quantize(KDELTA).unwrap() == wx.quantize(KDELTA).unwrap())
+                || (wy.quantize(self.delta).unwrap() == wx.quantize(self.delta).unwrap())
         } else if px.0.is_some() && py.0.is_none() {
             natural_less(&wy, &wx).unwrap()
-                && !(wy.quantize(KDELTA).unwrap() == wx.quantize(KDELTA).unwrap())
+                && !(wy.quantize(self.delta).unwrap() == wx.quantize(self.delta).unwrap())
         } else {
             natural_less(&wy, &wx).unwrap()
         };
@@ -329,12 +615,29 @@ impl<F: Fn(&usize, &usize) -> bool> Heap<F> {
     }
 }
 
-fn n_shortest_path<W, FI, FO>(ifst: &FI, distance: &[W], nshortest: usize) -> Result<FO>
+struct NShortestPathConfig<W: Semiring> {
+    nshortest: usize,
+    weight_threshold: W,
+    state_threshold: Option<StateId>,
+    delta: f32,
+}
+
+fn n_shortest_path<W, FI, FO>(
+    ifst: &FI,
+    distance: &[W],
+    config: NShortestPathConfig<W>,
+) -> Result<FO>
 where
     W: Semiring + WeightQuantize,
     FI: MutableFst<W::ReverseWeight>,
     FO: MutableFst<W>,
 {
+    let NShortestPathConfig {
+        nshortest,
+        weight_threshold,
+        state_threshold,
+        delta,
+    } = config;
     let mut ofst = FO::new();
     if nshortest == 0 {
         return Ok(ofst);
@@ -358,13 +661,11 @@ where
     let pairs = RefCell::new(vec![(None, W::zero()); final_state + 1]);
     pairs.borrow_mut()[final_state] = (Some(istart), W::one());
 
-    let shortest_path_compare = ShortestPathCompare::new(&pairs, distance);
+    let shortest_path_compare = ShortestPathCompare::new(&pairs, distance, delta);
 
     let mut heap = Heap::new(|v1, v2| shortest_path_compare.compare(*v1, *v2));
     heap.push(final_state);
 
-    let weight_threshold = W::zero();
-    let state_threshold: Option<StateId> = None;
     let limit = distance[istart].times(weight_threshold)?;
 
     let mut r = vec![];
@@ -436,3 +737,145 @@ where
 
     Ok(ofst)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::compose::{compose, ComposeFst};
+    use crate::algorithms::queues::FifoQueue;
+    use crate::fst_traits::PathsIterator;
+    use crate::semirings::TropicalWeight;
+
+    fn fst() -> Result<VectorFst<TropicalWeight>> {
+        let mut fst = VectorFst::new();
+        fst.add_states(4);
+        fst.set_start(0)?;
+        fst.set_final(3, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+        fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(2.0), 1))?;
+        fst.add_tr(0, Tr::new(3, 3, TropicalWeight::new(0.5), 2))?;
+        fst.add_tr(1, Tr::new(4, 4, TropicalWeight::new(1.0), 3))?;
+        fst.add_tr(2, Tr::new(5, 5, TropicalWeight::new(4.0), 3))?;
+        Ok(fst)
+    }
+
+    fn path_weights(fst: &VectorFst<TropicalWeight>) -> Vec<f32> {
+        let mut weights: Vec<_> = fst.paths_iter().map(|p| *p.weight.value()).collect();
+        weights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        weights
+    }
+
+    #[test]
+    fn test_shortest_path_with_config_weight_threshold() -> Result<()> {
+        let fst = fst()?;
+        let config = ShortestPathConfig::new_with_default(FifoQueue::default(), 3, false);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(path_weights(&res), vec![2.0, 3.0, 4.5]);
+
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 3, false);
+        config.weight_threshold = TropicalWeight::new(1.0);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(path_weights(&res), vec![2.0, 3.0]);
+
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 1, false);
+        config.weight_threshold = TropicalWeight::new(1.0);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(path_weights(&res), vec![2.0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_path_with_config_first_path() -> Result<()> {
+        let fst = fst()?;
+        // State 3 is first reached through state 1 in FIFO order.
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 1, false);
+        config.first_path = true;
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(path_weights(&res), vec![2.0]);
+
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 2, false);
+        config.first_path = true;
+        assert!(shortest_path_with_config::<_, _, VectorFst<_>, _>(&fst, config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_path_trace() -> Result<()> {
+        let fst = fst()?;
+        let trace = shortest_path_trace(&fst)?.unwrap();
+        assert_eq!(trace.states, vec![0, 1, 3]);
+        assert_eq!(trace.tr_indices, vec![0, 0]);
+        assert_eq!(
+            trace.path,
+            FstPath::new(vec![1, 4], vec![1, 4], TropicalWeight::new(2.0))
+        );
+
+        let best: VectorFst<TropicalWeight> = shortest_path(&fst, 1, false)?;
+        assert_eq!(best.paths_iter().next(), Some(trace.path));
+
+        // The start state is final.
+        let mut fst = fst;
+        fst.set_final(0, TropicalWeight::new(1.5))?;
+        let trace = shortest_path_trace(&fst)?.unwrap();
+        assert_eq!(trace.states, vec![0]);
+        assert!(trace.tr_indices.is_empty());
+        assert_eq!(trace.path.weight, TropicalWeight::new(1.5));
+
+        assert_eq!(
+            shortest_path_trace(&VectorFst::<TropicalWeight>::new())?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_path_with_config_state_threshold() -> Result<()> {
+        let fst = fst()?;
+        // Only states 0 and 1 can be reached, there is no path.
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 1, false);
+        config.state_threshold = Some(2);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(res.num_states(), 0);
+
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 3, false);
+        config.state_threshold = Some(2);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(res.num_states(), 0);
+
+        let mut config = ShortestPathConfig::new_with_default(FifoQueue::default(), 3, false);
+        config.state_threshold = Some(100);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&fst, config)?;
+        assert_eq!(path_weights(&res), vec![2.0, 3.0, 4.5]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_path_with_config_lazy_fst() -> Result<()> {
+        let fst1 = Arc::new(fst()?);
+        let mut fst2 = VectorFst::<TropicalWeight>::new();
+        fst2.add_states(1);
+        fst2.set_start(0)?;
+        fst2.set_final(0, TropicalWeight::one())?;
+        for label in 1..6 {
+            let weight = TropicalWeight::new(if label == 4 { 3.0 } else { 0.0 });
+            fst2.add_tr(0, Tr::new(label, label, weight, 0))?;
+        }
+        let fst2 = Arc::new(fst2);
+
+        let composed: VectorFst<TropicalWeight> = compose(fst1.clone(), fst2.clone())?;
+        for nshortest in 1..3 {
+            let lazy_fst = ComposeFst::new_auto(fst1.clone(), fst2.clone())?;
+            let config =
+                ShortestPathConfig::new_with_default(FifoQueue::default(), nshortest, false);
+            let res: VectorFst<TropicalWeight> = shortest_path_with_config(&lazy_fst, config)?;
+            let expected: VectorFst<TropicalWeight> = shortest_path(&composed, nshortest, false)?;
+            assert_eq!(path_weights(&res), path_weights(&expected));
+        }
+        let lazy_fst = ComposeFst::new_auto(fst1, fst2)?;
+        let config = ShortestPathConfig::new_with_default(FifoQueue::default(), 1, false);
+        let res: VectorFst<TropicalWeight> = shortest_path_with_config(&lazy_fst, config)?;
+        assert_eq!(path_weights(&res), vec![4.5]);
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use anyhow::Result;
+
+use crate::algorithms::queues::natural_less;
+use crate::algorithms::shortest_distance;
+use crate::fst_path::FstPath;
+use crate::fst_traits::ExpandedFst;
+use crate::semirings::{Semiring, SemiringProperties};
+use crate::{Label, StateId, Trs, EPS_LABEL};
+
+/// Node of the tree of the prefixes explored so far. Storing the labels of a prefix as a
+/// link to its parent avoids copying them for each extension.
+#[derive(Debug, Clone)]
+struct PrefixNode {
+    parent: Option<usize>,
+    ilabel: Label,
+    olabel: Label,
+}
+
+/// Prefix of a path waiting to be extended. `state` is `None` once the final weight has
+/// been added, the path is then complete.
+#[derive(Debug, Clone)]
+struct Candidate<W> {
+    /// Weight of the best complete path starting with this prefix.
+    priority: W,
+    weight: W,
+    state: Option<StateId>,
+    node: Option<usize>,
+    /// Insertion order, to break the ties.
+    id: usize,
+}
+
+impl<W: Semiring> PartialEq for Candidate<W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<W: Semiring> Eq for Candidate<W> {}
+
+impl<W: Semiring> PartialOrd for Candidate<W> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<W: Semiring> Ord for Candidate<W> {
+    /// `BinaryHeap` is a max-heap : the greatest candidate is the one with the lowest
+    /// priority, then the oldest one.
+    fn cmp(&self, other: &Self) -> Ordering {
+        if natural_less(&self.priority, &other.priority).unwrap() {
+            Ordering::Greater
+        } else if natural_less(&other.priority, &self.priority).unwrap() {
+            Ordering::Less
+        } else {
+            other.id.cmp(&self.id)
+        }
+    }
+}
+
+/// Iterator over the successful paths of an FST in increasing weight order w.r.t. the natural
+/// semiring order. See [`shortest_paths_iter`](fn.shortest_paths_iter.html).
+pub struct ShortestPathsIterator<'a, W: Semiring, F: ExpandedFst<W>> {
+    fst: &'a F,
+    /// Shortest distance from each state to the final states.
+    distance: Vec<W>,
+    heap: BinaryHeap<Candidate<W>>,
+    nodes: Vec<PrefixNode>,
+    num_candidates: usize,
+    seen: Option<HashSet<(Vec<Label>, Vec<Label>)>>,
+}
+
+/// Lazily enumerates the successful paths of an FST in increasing weight order w.r.t. the
+/// natural semiring order. The weights must have the path property, e.g `TropicalWeight`.
+///
+/// The shortest distance from each state to the final states is computed once, then the
+/// prefixes of the paths are extended in a best-first order : the exact weight of the best
+/// completion of a prefix is known, so each path is found after exploring only the prefixes
+/// of the paths lighter than it. Contrary to `shortest_path`, the number of paths doesn't need
+/// to be known in advance and the FST can be cyclic : the iterator is then infinite.
+///
+/// If `unique` is true, only the best path of each pair of input and output label
+/// sequences is returned. In that case the iteration doesn't terminate if the FST has
+/// infinitely many paths but finitely many label sequences, e.g with an epsilon cycle.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::algorithms::shortest_paths_iter;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::MutableFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::Tr;
+/// # fn main() -> Result<()> {
+/// let mut fst = VectorFst::<TropicalWeight>::new();
+/// fst.add_states(2);
+/// fst.set_start(0)?;
+/// fst.set_final(1, TropicalWeight::one())?;
+/// fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+/// fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(2.0), 1))?;
+/// fst.add_tr(1, Tr::new(3, 3, TropicalWeight::new(2.5), 1))?;
+///
+/// // The FST is cyclic but only the paths needed are explored.
+/// let paths: Vec<_> = shortest_paths_iter(&fst, false)?
+///     .filter(|p| p.ilabels[0] == 1)
+///     .take(2)
+///     .collect();
+/// assert_eq!(paths[0].ilabels, vec![1]);
+/// assert_eq!(paths[1].ilabels, vec![1, 3]);
+/// assert_eq!(paths[1].weight, TropicalWeight::new(3.5));
+/// # Ok(())
+/// # }
+/// ```
+pub fn shortest_paths_iter<W, F>(fst: &F, unique: bool) -> Result<ShortestPathsIterator<'_, W, F>>
+where
+    W: Semiring,
+    F: ExpandedFst<W>,
+{
+    if !W::properties().contains(SemiringProperties::PATH | SemiringProperties::SEMIRING) {
+        bail!("ShortestPathsIterator : Weight needs to have the path property and be distributive")
+    }
+    let mut distance = shortest_distance(fst, true)?;
+    distance.resize(fst.num_states(), W::zero());
+
+    let mut iter = ShortestPathsIterator {
+        fst,
+        distance,
+        heap: BinaryHeap::new(),
+        nodes: vec![],
+        num_candidates: 0,
+        seen: if unique { Some(HashSet::new()) } else { None },
+    };
+    if let Some(start) = fst.start() {
+        iter.push(W::one(), Some(start), None)?;
+    }
+    Ok(iter)
+}
+
+impl<'a, W: Semiring, F: ExpandedFst<W>> ShortestPathsIterator<'a, W, F> {
+    fn push(&mut self, weight: W, state: Option<StateId>, node: Option<usize>) -> Result<()> {
+        let priority = match state {
+            Some(s) => weight.times(&self.distance[s])?,
+            None => weight.clone(),
+        };
+        // Prefixes that can't be completed are dropped.
+        if priority.is_zero() {
+            return Ok(());
+        }
+        self.heap.push(Candidate {
+            priority,
+            weight,
+            state,
+            node,
+            id: self.num_candidates,
+        });
+        self.num_candidates += 1;
+        Ok(())
+    }
+
+    fn expand(&mut self, candidate: Candidate<W>, state: StateId) -> Result<()> {
+        let fst = self.fst;
+        for tr in fst.get_trs(state)?.trs() {
+            self.nodes.push(PrefixNode {
+                parent: candidate.node,
+                ilabel: tr.ilabel,
+                olabel: tr.olabel,
+            });
+            let node = Some(self.nodes.len() - 1);
+            self.push(
+                candidate.weight.times(&tr.weight)?,
+                Some(tr.nextstate),
+                node,
+            )?;
+        }
+        if let Some(final_weight) = fst.final_weight(state)? {
+            self.push(candidate.weight.times(final_weight)?, None, candidate.node)?;
+        }
+        Ok(())
+    }
+
+    fn path(&self, weight: W, mut node: Option<usize>) -> FstPath<W> {
+        let mut ilabels = vec![];
+        let mut olabels = vec![];
+        while let Some(n) = node {
+            let prefix_node = &self.nodes[n];
+            if prefix_node.ilabel != EPS_LABEL {
+                ilabels.push(prefix_node.ilabel);
+            }
+            if prefix_node.olabel != EPS_LABEL {
+                olabels.push(prefix_node.olabel);
+            }
+            node = prefix_node.parent;
+        }
+        ilabels.reverse();
+        olabels.reverse();
+        FstPath::new(ilabels, olabels, weight)
+    }
+}
+
+impl<'a, W: Semiring, F: ExpandedFst<W>> Iterator for ShortestPathsIterator<'a, W, F> {
+    type Item = FstPath<W>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(candidate) = self.heap.pop() {
+            match candidate.state {
+                Some(state) => self
+                    .expand(candidate, state)
+                    .expect("Error expand in ShortestPathsIterator"),
+                None => {
+                    let path = self.path(candidate.weight, candidate.node);
+                    if let Some(seen) = self.seen.as_mut() {
+                        if !seen.insert((path.ilabels.clone(), path.olabels.clone())) {
+                            continue;
+                        }
+                    }
+                    return Some(path);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::shortest_path;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{MutableFst, PathsIterator};
+    use crate::semirings::{IntegerWeight, TropicalWeight};
+    use crate::Tr;
+
+    fn weights(paths: &[FstPath<TropicalWeight>]) -> Vec<f32> {
+        paths.iter().map(|p| *p.weight.value()).collect()
+    }
+
+    #[test]
+    fn test_shortest_paths_iter_matches_shortest_path() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(4);
+        fst.set_start(0)?;
+        fst.set_final(3, TropicalWeight::new(0.5))?;
+        fst.set_final(2, TropicalWeight::new(4.0))?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+        fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(3.0), 1))?;
+        fst.add_tr(0, Tr::new(3, 3, TropicalWeight::new(0.0), 2))?;
+        fst.add_tr(1, Tr::new(4, 4, TropicalWeight::new(2.0), 3))?;
+        fst.add_tr(1, Tr::new(5, 5, TropicalWeight::new(0.25), 2))?;
+        fst.add_tr(2, Tr::new(6, 6, TropicalWeight::new(5.0), 3))?;
+
+        let paths: Vec<_> = shortest_paths_iter(&fst, false)?.collect();
+        let mut expected: Vec<_> = fst.paths_iter().collect();
+        expected.sort_by(|a, b| a.weight.value().partial_cmp(b.weight.value()).unwrap());
+        assert_eq!(weights(&paths), weights(&expected));
+        assert_eq!(paths.len(), 8);
+
+        let best: VectorFst<TropicalWeight> = shortest_path(&fst, 3, false)?;
+        let mut best_paths: Vec<_> = best.paths_iter().collect();
+        best_paths.sort_by(|a, b| a.weight.value().partial_cmp(b.weight.value()).unwrap());
+        assert_eq!(paths[..3].to_vec(), best_paths);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_paths_iter_unique() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(3);
+        fst.set_start(0)?;
+        fst.set_final(2, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 1))?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(2.0), 1))?;
+        fst.add_tr(
+            0,
+            Tr::new(EPS_LABEL, EPS_LABEL, TropicalWeight::new(4.0), 2),
+        )?;
+        fst.add_tr(1, Tr::new(2, 2, TropicalWeight::one(), 2))?;
+        fst.add_tr(
+            1,
+            Tr::new(EPS_LABEL, EPS_LABEL, TropicalWeight::new(1.5), 2),
+        )?;
+
+        let paths: Vec<_> = shortest_paths_iter(&fst, false)?.collect();
+        assert_eq!(weights(&paths), vec![1.0, 2.0, 2.5, 3.5, 4.0]);
+
+        let paths: Vec<_> = shortest_paths_iter(&fst, true)?.collect();
+        assert_eq!(
+            paths,
+            vec![
+                FstPath::new(vec![1, 2], vec![1, 2], TropicalWeight::new(1.0)),
+                FstPath::new(vec![1], vec![1], TropicalWeight::new(2.5)),
+                FstPath::new(vec![], vec![], TropicalWeight::new(4.0)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shortest_paths_iter_cyclic() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        fst.add_states(3);
+        fst.set_start(0)?;
+        fst.set_final(1, TropicalWeight::one())?;
+        fst.add_tr(0, Tr::new(1, 1, TropicalWeight::new(1.0), 0))?;
+        fst.add_tr(0, Tr::new(2, 2, TropicalWeight::new(0.5), 1))?;
+        // State 2 isn't coaccessible and is never explored.
+        fst.add_tr(0, Tr::new(3, 3, TropicalWeight::one(), 2))?;
+        fst.add_tr(2, Tr::new(3, 3, TropicalWeight::one(), 2))?;
+
+        let paths: Vec<_> = shortest_paths_iter(&fst, false)?.take(4).collect();
+        assert_eq!(weights(&paths), vec![0.5, 1.5, 2.5, 3.5]);
+        assert_eq!(paths[3].ilabels, vec![1, 1, 1, 2]);
+
+        let empty = VectorFst::<TropicalWeight>::new();
+        assert_eq!(shortest_paths_iter(&empty, false)?.count(), 0);
+        assert!(shortest_paths_iter(&VectorFst::<IntegerWeight>::new(), false).is_err());
+        Ok(())
+    }
+}
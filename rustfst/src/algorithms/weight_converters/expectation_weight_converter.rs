@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+
+use anyhow::Result;
+
+use crate::algorithms::{FinalTr, MapFinalAction, WeightConverter};
+use crate::semirings::{ExpectationWeight, LogWeight, ScalarTimes, Semiring};
+use crate::{Label, Tr};
+
+/// Mapper converting a `LogWeight` FST into an `ExpectationWeight` FST.
+/// Each weight `w` is mapped to `(w, w·f)` where `f` is the value of the feature
+/// computed from the input and output labels of the transition. Final weights are
+/// converted the same way using the labels of the `FinalTr`, i.e `EPS_LABEL` by default.
+/// Running `shortest_distance` on the result computes the expected value of the feature.
+pub struct ExpectationWeightConverter<W2, F> {
+    feature: F,
+    w2: PhantomData<W2>,
+}
+
+impl<W2, F> ExpectationWeightConverter<W2, F>
+where
+    W2: ScalarTimes<LogWeight> + Semiring<ReverseWeight = W2>,
+    F: FnMut(Label, Label) -> Result<W2>,
+{
+    pub fn new(feature: F) -> Self {
+        Self {
+            feature,
+            w2: PhantomData,
+        }
+    }
+
+    fn convert(
+        &mut self,
+        ilabel: Label,
+        olabel: Label,
+        weight: &LogWeight,
+    ) -> Result<ExpectationWeight<LogWeight, W2>> {
+        let value2 = (self.feature)(ilabel, olabel)?.scalar_times(weight)?;
+        Ok(ExpectationWeight::new((*weight, value2)))
+    }
+}
+
+impl<W2, F> WeightConverter<LogWeight, ExpectationWeight<LogWeight, W2>>
+    for ExpectationWeightConverter<W2, F>
+where
+    W2: ScalarTimes<LogWeight> + Semiring<ReverseWeight = W2>,
+    F: FnMut(Label, Label) -> Result<W2>,
+{
+    fn tr_map(&mut self, tr: &Tr<LogWeight>) -> Result<Tr<ExpectationWeight<LogWeight, W2>>> {
+        Ok(Tr::new(
+            tr.ilabel,
+            tr.olabel,
+            self.convert(tr.ilabel, tr.olabel, &tr.weight)?,
+            tr.nextstate,
+        ))
+    }
+
+    fn final_tr_map(
+        &mut self,
+        final_tr: &FinalTr<LogWeight>,
+    ) -> Result<FinalTr<ExpectationWeight<LogWeight, W2>>> {
+        Ok(FinalTr {
+            ilabel: final_tr.ilabel,
+            olabel: final_tr.olabel,
+            weight: self.convert(final_tr.ilabel, final_tr.olabel, &final_tr.weight)?,
+        })
+    }
+
+    fn final_action(&self) -> MapFinalAction {
+        MapFinalAction::MapNoSuperfinal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::{shortest_distance, weight_convert};
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::MutableFst;
+
+    #[test]
+    fn test_expectation_weight_converter() -> Result<()> {
+        let mut fst = VectorFst::<LogWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, -(0.25f32.ln()), s1))?;
+        fst.add_tr(s0, Tr::new(2, 2, -(0.75f32.ln()), s1))?;
+        fst.set_final(s1, LogWeight::one())?;
+
+        // Feature counting the occurrences of label 1.
+        let mut converter = ExpectationWeightConverter::new(|ilabel, _olabel| {
+            Ok(if ilabel == 1 {
+                LogWeight::one()
+            } else {
+                LogWeight::zero()
+            })
+        });
+        let fst_exp: VectorFst<ExpectationWeight<LogWeight, LogWeight>> =
+            weight_convert(&fst, &mut converter)?;
+        let distances = shortest_distance(&fst_exp, false)?;
+        let total = &distances[s1];
+        assert!(total.value1().value().abs() < 1e-6);
+        assert!((total.value2().value() + 0.25f32.ln()).abs() < 1e-6);
+        Ok(())
+    }
+}
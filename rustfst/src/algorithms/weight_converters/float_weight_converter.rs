@@ -0,0 +1,73 @@
+use anyhow::Result;
+use num_traits::AsPrimitive;
+
+use crate::algorithms::{FinalTr, MapFinalAction, WeightConverter};
+use crate::semirings::Semiring;
+use crate::Tr;
+
+/// Mapper that leaves labels and nextstate unchanged and constructs a new weight
+/// by casting the underlying float value of the transition weight.
+/// Useful to change the precision of a semiring, e.g. from `TropicalWeight` to `TropicalWeight64`.
+pub struct FloatWeightConverter {}
+
+impl<SI, SO> WeightConverter<SI, SO> for FloatWeightConverter
+where
+    SI: Semiring,
+    SO: Semiring,
+    SI::Type: AsPrimitive<SO::Type>,
+    SO::Type: Copy,
+{
+    fn tr_map(&mut self, tr: &Tr<SI>) -> Result<Tr<SO>> {
+        Ok(Tr::new(
+            tr.ilabel,
+            tr.olabel,
+            SO::new(tr.weight.value().as_()),
+            tr.nextstate,
+        ))
+    }
+
+    fn final_tr_map(&mut self, final_tr: &FinalTr<SI>) -> Result<FinalTr<SO>> {
+        Ok(FinalTr {
+            ilabel: final_tr.ilabel,
+            olabel: final_tr.olabel,
+            weight: SO::new(final_tr.weight.value().as_()),
+        })
+    }
+
+    fn final_action(&self) -> MapFinalAction {
+        MapFinalAction::MapNoSuperfinal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::weight_convert;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{CoreFst, MutableFst};
+    use crate::semirings::{LogWeight, LogWeight64, TropicalWeight, TropicalWeight64};
+
+    #[test]
+    fn test_float_weight_converter() -> Result<()> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 2, 0.5, s1))?;
+        fst.set_final(s1, 1.5)?;
+
+        let mut converter = FloatWeightConverter {};
+        let fst64: VectorFst<TropicalWeight64> = weight_convert(&fst, &mut converter)?;
+        assert_eq!(fst64.get_trs(s0)?[0].weight, TropicalWeight64::new(0.5));
+        assert_eq!(fst64.final_weight(s1)?, Some(TropicalWeight64::new(1.5)));
+
+        let fst_back: VectorFst<TropicalWeight> = weight_convert(&fst64, &mut converter)?;
+        assert_eq!(fst_back, fst);
+
+        let fst_log64: VectorFst<LogWeight64> = weight_convert(&fst, &mut converter)?;
+        let fst_log: VectorFst<LogWeight> = weight_convert(&fst_log64, &mut converter)?;
+        assert_eq!(fst_log.final_weight(s1)?, Some(LogWeight::new(1.5)));
+        Ok(())
+    }
+}
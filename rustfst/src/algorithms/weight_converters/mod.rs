@@ -1,7 +1,13 @@
+mod expectation_weight_converter;
+mod float_weight_converter;
 mod from_gallic_mapper;
+mod signed_log_weight_converter;
 mod simple_weight_converter;
 mod to_gallic_converter;
 
+pub use self::expectation_weight_converter::ExpectationWeightConverter;
+pub use self::float_weight_converter::FloatWeightConverter;
 pub use self::from_gallic_mapper::FromGallicConverter;
+pub use self::signed_log_weight_converter::SignedLogWeightConverter;
 pub use self::simple_weight_converter::SimpleWeightConverter;
 pub use self::to_gallic_converter::ToGallicConverter;
@@ -0,0 +1,118 @@
+use anyhow::Result;
+
+use crate::algorithms::{FinalTr, MapFinalAction, WeightConverter};
+use crate::semirings::{LogWeight, ProbabilityWeight, Semiring, SignedLogWeight};
+use crate::Tr;
+
+/// Mapper converting weights between `SignedLogWeight` and `LogWeight` or `ProbabilityWeight`.
+/// Labels and nextstate are left unchanged. Converting a negative `SignedLogWeight`
+/// to one of the unsigned semirings returns an error.
+pub struct SignedLogWeightConverter {}
+
+macro_rules! impl_signed_log_weight_converter {
+    ($si: ty, $so: ty, $convert: ident) => {
+        impl WeightConverter<$si, $so> for SignedLogWeightConverter {
+            fn tr_map(&mut self, tr: &Tr<$si>) -> Result<Tr<$so>> {
+                Ok(Tr::new(
+                    tr.ilabel,
+                    tr.olabel,
+                    $convert(&tr.weight)?,
+                    tr.nextstate,
+                ))
+            }
+
+            fn final_tr_map(&mut self, final_tr: &FinalTr<$si>) -> Result<FinalTr<$so>> {
+                Ok(FinalTr {
+                    ilabel: final_tr.ilabel,
+                    olabel: final_tr.olabel,
+                    weight: $convert(&final_tr.weight)?,
+                })
+            }
+
+            fn final_action(&self) -> MapFinalAction {
+                MapFinalAction::MapNoSuperfinal
+            }
+        }
+    };
+}
+
+fn check_positive(w: &SignedLogWeight) -> Result<()> {
+    if !w.is_positive() && w != &SignedLogWeight::zero() {
+        bail!(
+            "Can't convert negative weight {:?} to an unsigned semiring",
+            w
+        )
+    }
+    Ok(())
+}
+
+fn log_to_signed_log(w: &LogWeight) -> Result<SignedLogWeight> {
+    Ok(SignedLogWeight::new_signed(true, *w.value()))
+}
+
+fn signed_log_to_log(w: &SignedLogWeight) -> Result<LogWeight> {
+    check_positive(w)?;
+    Ok(*w.value2())
+}
+
+fn probability_to_signed_log(w: &ProbabilityWeight) -> Result<SignedLogWeight> {
+    Ok(SignedLogWeight::from_real(*w.value()))
+}
+
+fn signed_log_to_probability(w: &SignedLogWeight) -> Result<ProbabilityWeight> {
+    check_positive(w)?;
+    Ok(ProbabilityWeight::new(w.to_real()))
+}
+
+impl_signed_log_weight_converter!(LogWeight, SignedLogWeight, log_to_signed_log);
+impl_signed_log_weight_converter!(SignedLogWeight, LogWeight, signed_log_to_log);
+impl_signed_log_weight_converter!(
+    ProbabilityWeight,
+    SignedLogWeight,
+    probability_to_signed_log
+);
+impl_signed_log_weight_converter!(
+    SignedLogWeight,
+    ProbabilityWeight,
+    signed_log_to_probability
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::weight_convert;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{CoreFst, MutableFst};
+
+    #[test]
+    fn test_signed_log_weight_converter() -> Result<()> {
+        let mut fst = VectorFst::<LogWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 2, 0.5, s1))?;
+        fst.set_final(s1, 1.5)?;
+
+        let mut converter = SignedLogWeightConverter {};
+        let fst_signed: VectorFst<SignedLogWeight> = weight_convert(&fst, &mut converter)?;
+        assert_eq!(
+            fst_signed.final_weight(s1)?,
+            Some(SignedLogWeight::new_signed(true, 1.5))
+        );
+        let fst_back: VectorFst<LogWeight> = weight_convert(&fst_signed, &mut converter)?;
+        assert_eq!(fst_back, fst);
+
+        let fst_proba: VectorFst<ProbabilityWeight> = weight_convert(&fst_signed, &mut converter)?;
+        let w = fst_proba.get_trs(s0)?[0].weight;
+        assert!((w.value() - (-0.5f32).exp()).abs() < 1e-6);
+        let fst_signed_2: VectorFst<SignedLogWeight> = weight_convert(&fst_proba, &mut converter)?;
+        assert!((fst_signed_2.get_trs(s0)?[0].weight.value2().value() - 0.5).abs() < 1e-6);
+
+        let mut fst_negative = fst_signed;
+        fst_negative.set_final(s1, SignedLogWeight::new_signed(false, 1.5))?;
+        let res: Result<VectorFst<LogWeight>> = weight_convert(&fst_negative, &mut converter);
+        assert!(res.is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use crate::{StateId, SymbolTable};
+
 /// Struct to configure how the FST should be drawn.
 #[derive(Debug, Clone, PartialEq)]
 pub struct DrawingConfig {
@@ -21,6 +25,20 @@ pub struct DrawingConfig {
     pub show_weight_one: bool,
     /// Print/draw transition weights and final weights.
     pub print_weight: bool,
+    /// Input `SymbolTable` used to display the input labels.
+    /// If `None`, the one attached to the FST is used (if any).
+    pub isymbols: Option<Arc<SymbolTable>>,
+    /// Output `SymbolTable` used to display the output labels.
+    /// If `None`, the one attached to the FST is used (if any).
+    pub osymbols: Option<Arc<SymbolTable>>,
+    /// Path to highlight, as a sequence of transitions. Each transition is identified by
+    /// its source state and its index in the list of transitions leaving that state.
+    pub highlight_path: Option<Vec<(StateId, usize)>>,
+    /// Maximum number of states to draw. States are drawn in breadth-first order from the
+    /// start state and transitions leading to states that are not drawn are omitted.
+    pub max_states: Option<usize>,
+    /// Fill the states with a colour depending on the strongly connected component they belong to.
+    pub color_sccs: bool,
 }
 
 impl Default for DrawingConfig {
@@ -36,6 +54,11 @@ impl Default for DrawingConfig {
             acceptor: false,
             show_weight_one: true,
             print_weight: true,
+            isymbols: None,
+            osymbols: None,
+            highlight_path: None,
+            max_states: None,
+            color_sccs: false,
         }
     }
 }
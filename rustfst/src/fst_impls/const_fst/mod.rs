@@ -6,4 +6,6 @@ mod expanded_fst;
 mod fst;
 mod iterators;
 mod misc;
+#[cfg(feature = "serde")]
+mod serde_fst;
 mod serializable_fst;
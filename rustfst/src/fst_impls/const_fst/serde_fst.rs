@@ -0,0 +1,18 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::fst_impls::ConstFst;
+use crate::parsers::serde_utils::{deserialize_vector_fst, serialize_fst};
+use crate::semirings::Semiring;
+
+/// Same schema as `VectorFst`.
+impl<W: Semiring + Serialize> Serialize for ConstFst<W> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fst(self, serializer)
+    }
+}
+
+impl<'de, W: 'static + Semiring + Deserialize<'de>> Deserialize<'de> for ConstFst<W> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_vector_fst(deserializer).map(|fst| fst.into())
+    }
+}
@@ -10,6 +10,38 @@ use crate::{StateId, TrsVec};
 ///
 /// All states are stored in a vector of states.
 /// In each state, there is a vector of trs containing the outgoing transitions.
+///
+/// # JSON serialization
+///
+/// With the `serde` cargo feature enabled, `VectorFst` and `ConstFst` implement `Serialize` and
+/// `Deserialize` as long as the weight does. The FST is represented as follows :
+///
+/// ```json
+/// {
+///   "start": 0,
+///   "states": [
+///     {
+///       "final_weight": null,
+///       "trs": [{"ilabel": 1, "olabel": 2, "weight": 0.5, "nextstate": 1}]
+///     },
+///     {"final_weight": 1.5, "trs": []}
+///   ],
+///   "input_symbols": [[0, "<eps>"], [1, "a"], [2, "b"]],
+///   "output_symbols": null
+/// }
+/// ```
+///
+/// - `start` is the id of the start state or `null` if the FST is empty.
+/// - `states` is indexed by state id. `final_weight` is `null` for non-final states.
+/// - `input_symbols` and `output_symbols` are the attached `SymbolTable`s,
+///   as lists of `[label, symbol]` pairs, or `null`.
+///
+/// The weights are serialized as follows :
+/// - `TropicalWeight`, `LogWeight` and `ProbabilityWeight` : a number, or one of the strings
+///   `"Infinity"`, `"-Infinity"` and `"NaN"`.
+/// - `BooleanWeight` : a boolean. `IntegerWeight` : an integer.
+/// - String weights : a list of labels, or `null` for the infinite string.
+/// - `ProductWeight` and Gallic weights : a pair `[w1, w2]`. `UnionWeight` : a list of weights.
 #[derive(Debug, PartialEq, Clone)]
 pub struct VectorFst<W: Semiring> {
     pub(crate) states: Vec<VectorFstState<W>>,
@@ -1,5 +1,6 @@
 pub use self::data_structure::VectorFst;
 pub(crate) use self::data_structure::VectorFstState;
+pub(crate) use self::serializable_fst::parse_vector_fst;
 
 mod allocable_fst;
 mod data_structure;
@@ -8,5 +9,7 @@ mod fst;
 mod iterators;
 mod misc;
 mod mutable_fst;
+#[cfg(feature = "serde")]
+mod serde_fst;
 mod serializable_fst;
 mod test;
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::fst_impls::VectorFst;
+use crate::parsers::serde_utils::{deserialize_vector_fst, serialize_fst};
+use crate::semirings::Semiring;
+
+impl<W: Semiring + Serialize> Serialize for VectorFst<W> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_fst(self, serializer)
+    }
+}
+
+impl<'de, W: Semiring + Deserialize<'de>> Deserialize<'de> for VectorFst<W> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_vector_fst(deserializer)
+    }
+}
@@ -1,5 +1,5 @@
 use std::fs::{read, File};
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
 use std::path::Path;
 
 use anyhow::Context;
@@ -40,7 +40,45 @@ impl<W: 'static + SerializableSemiring> SerializableFst<W> for VectorFst<W> {
 
     fn write<P: AsRef<Path>>(&self, path_bin_fst: P) -> Result<()> {
         let mut file = BufWriter::new(File::create(path_bin_fst)?);
+        self.write_binary(&mut file)
+    }
+
+    fn from_parsed_fst_text(parsed_fst_text: ParsedTextFst<W>) -> Result<Self> {
+        let start_state = parsed_fst_text.start();
+        let num_states = parsed_fst_text.num_states();
+
+        let states = vec![VectorFstState::<W>::new(); num_states];
+
+        let mut fst = VectorFst {
+            states,
+            start_state,
+            isymt: None,
+            osymt: None,
+        };
+
+        for transition in parsed_fst_text.transitions.into_iter() {
+            let weight = transition.weight.unwrap_or_else(W::one);
+            let tr = Tr::new(
+                transition.ilabel,
+                transition.olabel,
+                weight,
+                transition.nextstate,
+            );
+            fst.add_tr(transition.state, tr)?;
+        }
+
+        for final_state in parsed_fst_text.final_states.into_iter() {
+            let weight = final_state.weight.unwrap_or_else(W::one);
+            fst.set_final(final_state.state, weight)?;
+        }
+
+        Ok(fst)
+    }
+}
 
+impl<W: 'static + SerializableSemiring> VectorFst<W> {
+    /// Writes the FST in binary format into any writer, e.g an in-memory buffer.
+    pub(crate) fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
         let num_trs: usize = (0..self.num_states())
             .map(|s: usize| unsafe { self.num_trs_unchecked(s) })
             .sum();
@@ -68,56 +106,24 @@ impl<W: 'static + SerializableSemiring> SerializableFst<W> for VectorFst<W> {
             isymt: self.input_symbols().cloned(),
             osymt: self.output_symbols().cloned(),
         };
-        hdr.write(&mut file)?;
+        hdr.write(file)?;
 
         // FstBody
         for state in 0..self.num_states() {
             let f_weight = unsafe { self.final_weight_unchecked(state).unwrap_or_else(W::zero) };
-            f_weight.write_binary(&mut file)?;
-            write_bin_i64(&mut file, unsafe { self.num_trs_unchecked(state) } as i64)?;
+            f_weight.write_binary(file)?;
+            write_bin_i64(file, unsafe { self.num_trs_unchecked(state) } as i64)?;
 
             for tr in unsafe { self.get_trs_unchecked(state).trs() } {
-                write_bin_i32(&mut file, tr.ilabel as i32)?;
-                write_bin_i32(&mut file, tr.olabel as i32)?;
-                tr.weight.write_binary(&mut file)?;
-                write_bin_i32(&mut file, tr.nextstate as i32)?;
+                write_bin_i32(file, tr.ilabel as i32)?;
+                write_bin_i32(file, tr.olabel as i32)?;
+                tr.weight.write_binary(file)?;
+                write_bin_i32(file, tr.nextstate as i32)?;
             }
         }
 
         Ok(())
     }
-
-    fn from_parsed_fst_text(parsed_fst_text: ParsedTextFst<W>) -> Result<Self> {
-        let start_state = parsed_fst_text.start();
-        let num_states = parsed_fst_text.num_states();
-
-        let states = vec![VectorFstState::<W>::new(); num_states];
-
-        let mut fst = VectorFst {
-            states,
-            start_state,
-            isymt: None,
-            osymt: None,
-        };
-
-        for transition in parsed_fst_text.transitions.into_iter() {
-            let weight = transition.weight.unwrap_or_else(W::one);
-            let tr = Tr::new(
-                transition.ilabel,
-                transition.olabel,
-                weight,
-                transition.nextstate,
-            );
-            fst.add_tr(transition.state, tr)?;
-        }
-
-        for final_state in parsed_fst_text.final_states.into_iter() {
-            let weight = final_state.weight.unwrap_or_else(W::one);
-            fst.set_final(final_state.state, weight)?;
-        }
-
-        Ok(fst)
-    }
 }
 
 static VECTOR_MIN_FILE_VERSION: i32 = 2;
@@ -143,7 +149,9 @@ fn parse_vector_fst_state<W: SerializableSemiring>(i: &[u8]) -> IResult<&[u8], V
     ))
 }
 
-fn parse_vector_fst<W: SerializableSemiring + 'static>(i: &[u8]) -> IResult<&[u8], VectorFst<W>> {
+pub(crate) fn parse_vector_fst<W: SerializableSemiring + 'static>(
+    i: &[u8],
+) -> IResult<&[u8], VectorFst<W>> {
     let (i, header) = FstHeader::parse(
         i,
         VECTOR_MIN_FILE_VERSION,
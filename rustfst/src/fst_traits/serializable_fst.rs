@@ -1,3 +1,4 @@
+use std::collections::{HashSet, VecDeque};
 use std::fs::File;
 use std::io::{BufWriter, LineWriter, Write};
 use std::path::Path;
@@ -5,6 +6,9 @@ use std::path::Path;
 use anyhow::Result;
 use unsafe_unwrap::UnsafeUnwrap;
 
+use crate::algorithms::dfs_visit::dfs_visit;
+use crate::algorithms::tr_filters::AnyTrFilter;
+use crate::algorithms::visitors::SccVisitor;
 use crate::fst_traits::ExpandedFst;
 use crate::parsers::text_fst::ParsedTextFst;
 use crate::semirings::SerializableSemiring;
@@ -92,12 +96,20 @@ pub trait SerializableFst<W: SerializableSemiring>: ExpandedFst<W> {
                 writeln!(f, "nodesep = {}", nodesep)?;
             }
 
-            // Start state first
-            draw_single_fst_state(self, &mut f, start_state, config)?;
+            let info = DrawingInfo::new(self, start_state, config)?;
 
-            for state in self.states_iter() {
-                if state != start_state {
-                    draw_single_fst_state(self, &mut f, state, config)?;
+            if config.max_states.is_some() {
+                for state in info.drawn_states.iter().flatten() {
+                    draw_single_fst_state(self, &mut f, *state, config, &info)?;
+                }
+            } else {
+                // Start state first
+                draw_single_fst_state(self, &mut f, start_state, config, &info)?;
+
+                for state in self.states_iter() {
+                    if state != start_state {
+                        draw_single_fst_state(self, &mut f, state, config, &info)?;
+                    }
                 }
             }
 
@@ -107,14 +119,112 @@ pub trait SerializableFst<W: SerializableSemiring>: ExpandedFst<W> {
     }
 }
 
+/// Colours used to fill the states of the different SCCs.
+static SCC_COLORS: [&str; 12] = [
+    "lightblue",
+    "lightpink",
+    "palegreen",
+    "khaki",
+    "plum",
+    "lightsalmon",
+    "paleturquoise",
+    "wheat",
+    "thistle",
+    "lightcyan",
+    "peachpuff",
+    "lavender",
+];
+
+/// Information computed from the `DrawingConfig` before drawing the states.
+struct DrawingInfo {
+    /// States to draw in order. `None` if all the states must be drawn.
+    drawn_states: Option<Vec<StateId>>,
+    drawn_states_set: Option<HashSet<StateId>>,
+    /// SCC of each state. `None` if the SCCs must not be coloured.
+    scc: Option<Vec<i32>>,
+    highlighted_trs: HashSet<(StateId, usize)>,
+    highlighted_states: HashSet<StateId>,
+}
+
+impl DrawingInfo {
+    fn new<W: SerializableSemiring, F: SerializableFst<W>>(
+        fst: &F,
+        start_state: StateId,
+        config: &DrawingConfig,
+    ) -> Result<Self> {
+        let drawn_states = config.max_states.map(|max_states| {
+            let mut visited = vec![false; fst.num_states()];
+            let mut drawn_states = Vec::with_capacity(max_states.min(fst.num_states()));
+            let mut queue = VecDeque::new();
+            visited[start_state] = true;
+            queue.push_back(start_state);
+            while let Some(state) = queue.pop_front() {
+                if drawn_states.len() >= max_states {
+                    break;
+                }
+                drawn_states.push(state);
+                for tr in unsafe { fst.get_trs_unchecked(state).trs() } {
+                    if !visited[tr.nextstate] {
+                        visited[tr.nextstate] = true;
+                        queue.push_back(tr.nextstate);
+                    }
+                }
+            }
+            drawn_states
+        });
+
+        let drawn_states_set = drawn_states
+            .as_ref()
+            .map(|drawn_states| drawn_states.iter().cloned().collect());
+
+        let scc = if config.color_sccs {
+            let mut visitor = SccVisitor::new(fst, true, false);
+            dfs_visit(fst, &mut visitor, &AnyTrFilter {}, false);
+            visitor.scc
+        } else {
+            None
+        };
+
+        let mut highlighted_trs = HashSet::new();
+        let mut highlighted_states = HashSet::new();
+        if let Some(path) = &config.highlight_path {
+            for &(state, tr_idx) in path {
+                let trs = fst.get_trs(state)?;
+                let tr = trs.trs().get(tr_idx).ok_or_else(|| {
+                    format_err!("Transition {} doesn't exist in state {}", tr_idx, state)
+                })?;
+                highlighted_trs.insert((state, tr_idx));
+                highlighted_states.insert(state);
+                highlighted_states.insert(tr.nextstate);
+            }
+        }
+
+        Ok(Self {
+            drawn_states,
+            drawn_states_set,
+            scc,
+            highlighted_trs,
+            highlighted_states,
+        })
+    }
+
+    fn is_drawn(&self, state: StateId) -> bool {
+        match &self.drawn_states_set {
+            Some(drawn_states_set) => drawn_states_set.contains(&state),
+            None => true,
+        }
+    }
+}
+
 fn draw_single_fst_state<S: SerializableSemiring, F: SerializableFst<S>, W: Write>(
     fst: &F,
     writer: &mut W,
     state_id: StateId,
     config: &DrawingConfig,
+    info: &DrawingInfo,
 ) -> Result<()> {
-    let opt_isymt = fst.input_symbols();
-    let opt_osymt = fst.output_symbols();
+    let opt_isymt = config.isymbols.as_ref().or_else(|| fst.input_symbols());
+    let opt_osymt = config.osymbols.as_ref().or_else(|| fst.output_symbols());
 
     write!(writer, "{}", state_id)?;
     write!(writer, " [label = \"{}", state_id)?;
@@ -127,18 +237,35 @@ fn draw_single_fst_state<S: SerializableSemiring, F: SerializableFst<S>, W: Writ
         write!(writer, "\", shape = circle,")?;
     }
 
-    if fst.is_start(state_id) {
-        write!(writer, " style = bold,")?;
+    let style = if fst.is_start(state_id) {
+        "bold"
+    } else {
+        "solid"
+    };
+    if let Some(scc) = &info.scc {
+        let color = SCC_COLORS[scc[state_id] as usize % SCC_COLORS.len()];
+        write!(
+            writer,
+            " style = \"{},filled\", fillcolor = {},",
+            style, color
+        )?;
     } else {
-        write!(writer, " style = solid,")?;
+        write!(writer, " style = {},", style)?;
+    }
+
+    if info.highlighted_states.contains(&state_id) {
+        write!(writer, " color = red,")?;
     }
 
     writeln!(writer, " fontsize = {}]", config.fontsize)?;
 
-    for tr in fst.get_trs(state_id).unwrap().trs() {
+    for (tr_idx, tr) in fst.get_trs(state_id).unwrap().trs().iter().enumerate() {
+        if !info.is_drawn(tr.nextstate) {
+            continue;
+        }
         write!(writer, "\t{} -> {}", state_id, tr.nextstate)?;
 
-        let ilabel = opt_isymt.clone().map_or_else(
+        let ilabel = opt_isymt.map_or_else(
             || Ok(format!("{}", tr.ilabel)),
             |symt| {
                 symt.get_symbol(tr.ilabel)
@@ -147,7 +274,7 @@ fn draw_single_fst_state<S: SerializableSemiring, F: SerializableFst<S>, W: Writ
             },
         )?;
 
-        let olabel = opt_osymt.clone().map_or_else(
+        let olabel = opt_osymt.map_or_else(
             || Ok(format!("{}", tr.olabel)),
             |symt| {
                 symt.get_symbol(tr.olabel)
@@ -164,8 +291,117 @@ fn draw_single_fst_state<S: SerializableSemiring, F: SerializableFst<S>, W: Writ
         if config.print_weight && (config.show_weight_one || !tr.weight.is_one()) {
             write!(writer, "/{}", tr.weight)?;
         }
-        writeln!(writer, "\", fontsize = {}];", config.fontsize)?;
+        write!(writer, "\", fontsize = {}", config.fontsize)?;
+        if info.highlighted_trs.contains(&(state_id, tr_idx)) {
+            write!(writer, ", color = red, penwidth = 2")?;
+        }
+        writeln!(writer, "];")?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::read_to_string;
+    use std::sync::Arc;
+
+    use tempfile::tempdir;
+
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::MutableFst;
+    use crate::semirings::{Semiring, TropicalWeight};
+    use crate::{SymbolTable, Tr};
+
+    fn draw_to_string(fst: &VectorFst<TropicalWeight>, config: &DrawingConfig) -> Result<String> {
+        let dir = tempdir()?;
+        let path = dir.path().join("fst.dot");
+        fst.draw(&path, config)?;
+        Ok(read_to_string(&path)?)
+    }
+
+    fn build_fst() -> Result<VectorFst<TropicalWeight>> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 2, 1.0, s1))?;
+        fst.add_tr(s0, Tr::new(2, 1, 3.0, s1))?;
+        fst.add_tr(s1, Tr::new(1, 1, 1.0, s0))?;
+        fst.add_tr(s1, Tr::new(2, 2, 1.0, s2))?;
+        fst.set_final(s2, TropicalWeight::one())?;
+        Ok(fst)
+    }
+
+    #[test]
+    fn test_draw_with_symbol_tables() -> Result<()> {
+        let fst = build_fst()?;
+        let mut isymt = SymbolTable::new();
+        isymt.add_symbols(vec!["a", "b"]);
+        let mut osymt = SymbolTable::new();
+        osymt.add_symbols(vec!["c", "d"]);
+
+        let config = DrawingConfig {
+            isymbols: Some(Arc::new(isymt)),
+            osymbols: Some(Arc::new(osymt)),
+            ..Default::default()
+        };
+
+        let dot = draw_to_string(&fst, &config)?;
+        assert!(dot.contains("0 -> 1 [label = \"a:d/1\", fontsize = 14];"));
+        assert!(dot.contains("0 -> 1 [label = \"b:c/3\", fontsize = 14];"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_highlight_path() -> Result<()> {
+        let fst = build_fst()?;
+        let mut config = DrawingConfig {
+            highlight_path: Some(vec![(0, 0), (1, 1)]),
+            ..Default::default()
+        };
+
+        let dot = draw_to_string(&fst, &config)?;
+        assert!(
+            dot.contains("0 -> 1 [label = \"1:2/1\", fontsize = 14, color = red, penwidth = 2];")
+        );
+        assert!(dot.contains("0 -> 1 [label = \"2:1/3\", fontsize = 14];"));
+        assert!(
+            dot.contains("1 -> 2 [label = \"2:2/1\", fontsize = 14, color = red, penwidth = 2];")
+        );
+        assert!(
+            dot.contains("2 [label = \"2/0\", shape = doublecircle, style = solid, color = red,")
+        );
+
+        config.highlight_path = Some(vec![(0, 2)]);
+        assert!(draw_to_string(&fst, &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_max_states_and_sccs() -> Result<()> {
+        let fst = build_fst()?;
+        let config = DrawingConfig {
+            max_states: Some(2),
+            color_sccs: true,
+            ..Default::default()
+        };
+
+        let dot = draw_to_string(&fst, &config)?;
+        assert!(!dot.contains("2 [label"));
+        assert!(!dot.contains("1 -> 2"));
+
+        let fill_color = |state: StateId| {
+            dot.lines()
+                .find(|l| l.starts_with(&format!("{} [label", state)))
+                .and_then(|l| l.split("fillcolor = ").nth(1))
+                .map(|l| l.split(',').next().unwrap().to_string())
+        };
+        assert!(fill_color(0).is_some());
+        assert_eq!(fill_color(0), fill_color(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,482 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use nom::bytes::complete::{tag, take_till1};
+use nom::combinator::{map_res, opt};
+use nom::error::ErrorKind;
+use nom::{IResult, Needed};
+
+use crate::fst_impls::vector_fst::parse_vector_fst;
+use crate::fst_impls::VectorFst;
+use crate::semirings::SerializableSemiring;
+
+/// Header written by Kaldi in front of every object stored in binary mode.
+const KALDI_BINARY_HEADER: &[u8] = b"\0B";
+
+/// Number of bytes read at once from the underlying reader.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Incrementally parses objects from a reader. The data is read by chunks and
+/// a parse is retried with more data when it runs out of input before the end of
+/// the reader. Any other parse error is returned immediately.
+struct StreamParser<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> StreamParser<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![],
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Reads at least `n` more bytes, unless the end of the input is reached.
+    fn fill(&mut self, n: usize) -> Result<()> {
+        if self.pos > 0 {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+        }
+        let target = self.buffer.len() + n;
+        while !self.eof && self.buffer.len() < target {
+            let len = self.buffer.len();
+            self.buffer.resize(target, 0);
+            let read = self.reader.read(&mut self.buffer[len..])?;
+            self.buffer.truncate(len + read);
+            if read == 0 {
+                self.eof = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_empty(&mut self) -> Result<bool> {
+        if self.pos == self.buffer.len() {
+            self.fill(CHUNK_SIZE)?;
+        }
+        Ok(self.pos == self.buffer.len())
+    }
+
+    fn parse<T, P>(&mut self, parser: P) -> Result<T>
+    where
+        P: Fn(&[u8]) -> IResult<&[u8], T>,
+    {
+        loop {
+            let available = self.buffer.len() - self.pos;
+            let input = &self.buffer[self.pos..];
+            match parser(input).map_err(|e| incomplete_at_end(input, e)) {
+                Ok((rest, res)) => {
+                    self.pos = self.buffer.len() - rest.len();
+                    return Ok(res);
+                }
+                Err(nom::Err::Incomplete(_)) => {
+                    if self.eof {
+                        bail!("Error while parsing Kaldi table : unexpected end of input");
+                    }
+                    self.fill(available.max(CHUNK_SIZE))?;
+                }
+                Err(e) => bail!("Error while parsing Kaldi table : {:?}", e),
+            }
+        }
+    }
+}
+
+/// The FST parsers work on complete inputs : running out of data is reported as an
+/// `Eof` error or as an error on an empty input. Such errors at the end of `input` are
+/// turned into `Incomplete` so that only these are retried with more data.
+fn incomplete_at_end<'a>(
+    input: &'a [u8],
+    e: nom::Err<(&'a [u8], ErrorKind)>,
+) -> nom::Err<(&'a [u8], ErrorKind)> {
+    match e {
+        nom::Err::Error((rest, kind)) | nom::Err::Failure((rest, kind))
+            if rest.as_ptr_range().end == input.as_ptr_range().end
+                && (rest.is_empty() || kind == ErrorKind::Eof) =>
+        {
+            nom::Err::Incomplete(Needed::Unknown)
+        }
+        e => e,
+    }
+}
+
+fn parse_key(i: &[u8]) -> IResult<&[u8], String> {
+    let (i, key) = map_res(take_till1(|c| c == b' '), std::str::from_utf8)(i)?;
+    let (i, _) = tag(" ")(i)?;
+    Ok((i, key.to_string()))
+}
+
+fn parse_kaldi_fst<W: SerializableSemiring + 'static>(i: &[u8]) -> IResult<&[u8], VectorFst<W>> {
+    let (i, _) = opt(tag(KALDI_BINARY_HEADER))(i)?;
+    parse_vector_fst(i)
+}
+
+fn parse_ark_entry<W: SerializableSemiring + 'static>(
+    i: &[u8],
+) -> IResult<&[u8], (String, VectorFst<W>)> {
+    let (i, key) = parse_key(i)?;
+    let (i, fst) = parse_kaldi_fst(i)?;
+    Ok((i, (key, fst)))
+}
+
+/// Lazily reads the (key, FST) pairs stored in a binary Kaldi archive (`ark` file).
+/// An archive is a sequence of entries made of a key, a space and a binary FST
+/// preceded by the `\0B` Kaldi binary header. FSTs and lattices can be read by
+/// picking the appropriate semiring, e.g `CompactLatticeWeight` for Kaldi lattices.
+///
+/// # Example
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// use rustfst::fst_traits::CoreFst;
+/// use rustfst::kaldi_table::ArkReader;
+/// use rustfst::semirings::CompactLatticeWeight;
+/// # fn main() -> Result<()> {
+/// for entry in ArkReader::<CompactLatticeWeight, _>::open("lat.1.ark")? {
+///     let (key, lattice) = entry?;
+///     println!("{} : {:?}", key, lattice.start());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArkReader<W, R: Read> {
+    parser: StreamParser<R>,
+    done: bool,
+    w: PhantomData<W>,
+}
+
+impl<W: SerializableSemiring + 'static> ArkReader<W, BufReader<File>> {
+    /// Opens the archive located at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Can't open Kaldi archive : {:?}", path.as_ref()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<W: SerializableSemiring + 'static, R: Read> ArkReader<W, R> {
+    /// Reads an archive from any reader, e.g the standard input.
+    pub fn new(reader: R) -> Self {
+        Self {
+            parser: StreamParser::new(reader),
+            done: false,
+            w: PhantomData,
+        }
+    }
+
+    fn read_entry(&mut self) -> Result<Option<(String, VectorFst<W>)>> {
+        if self.parser.is_empty()? {
+            return Ok(None);
+        }
+        self.parser.parse(parse_ark_entry).map(Some)
+    }
+}
+
+impl<W: SerializableSemiring + 'static, R: Read> Iterator for ArkReader<W, R> {
+    type Item = Result<(String, VectorFst<W>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_entry();
+        match res {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Lazily reads the (key, FST) pairs listed in a Kaldi script file (`scp` file).
+/// Each line contains a key and the location of the FST : either a path to a file
+/// containing a single FST or `path:offset` pointing to an FST inside an archive.
+pub struct ScpReader<W> {
+    lines: Lines<BufReader<File>>,
+    opened: Option<(PathBuf, BufReader<File>)>,
+    w: PhantomData<W>,
+}
+
+impl<W: SerializableSemiring + 'static> ScpReader<W> {
+    /// Opens the script file located at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Can't open Kaldi script file : {:?}", path.as_ref()))?;
+        Ok(Self {
+            lines: BufReader::new(file).lines(),
+            opened: None,
+            w: PhantomData,
+        })
+    }
+
+    fn read_fst(&mut self, location: &str) -> Result<VectorFst<W>> {
+        let (path, offset) = match location.rfind(':') {
+            Some(idx) => match location[idx + 1..].parse::<u64>() {
+                Ok(offset) => (&location[..idx], offset),
+                Err(_) => (location, 0),
+            },
+            None => (location, 0),
+        };
+        let path = PathBuf::from(path);
+
+        // Consecutive entries usually point to the same archive : keep it opened.
+        let mut reader = match self.opened.take() {
+            Some((opened_path, reader)) if opened_path == path => reader,
+            _ => BufReader::new(
+                File::open(&path)
+                    .with_context(|| format!("Can't open Kaldi archive : {:?}", path))?,
+            ),
+        };
+        reader.seek(SeekFrom::Start(offset))?;
+        let fst = StreamParser::new(&mut reader).parse(parse_kaldi_fst)?;
+        self.opened = Some((path, reader));
+        Ok(fst)
+    }
+}
+
+impl<W: SerializableSemiring + 'static> Iterator for ScpReader<W> {
+    type Item = Result<(String, VectorFst<W>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut split = line.splitn(2, char::is_whitespace);
+            let key = split.next().unwrap_or_default().to_string();
+            let location = match split.next() {
+                Some(location) => location.trim(),
+                None => {
+                    return Some(Err(format_err!(
+                        "Invalid line in Kaldi script file : {:?}",
+                        line
+                    )))
+                }
+            };
+            return Some(self.read_fst(location).map(|fst| (key, fst)));
+        }
+    }
+}
+
+/// Writes (key, FST) pairs in a binary Kaldi archive (`ark` file), optionally
+/// alongside a script file (`scp` file) containing the offset of each FST.
+pub struct ArkWriter<F: Write> {
+    writer: F,
+    offset: u64,
+    scp: Option<(String, BufWriter<File>)>,
+}
+
+impl ArkWriter<BufWriter<File>> {
+    /// Creates the archive located at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::create(path.as_ref())
+            .with_context(|| format!("Can't create Kaldi archive : {:?}", path.as_ref()))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    /// Creates the archive located at `ark_path` and the script file located at `scp_path`
+    /// listing the FSTs written in the archive.
+    pub fn create_with_scp<P1: AsRef<Path>, P2: AsRef<Path>>(
+        ark_path: P1,
+        scp_path: P2,
+    ) -> Result<Self> {
+        let mut writer = Self::create(ark_path.as_ref())?;
+        let scp_file = File::create(scp_path.as_ref())
+            .with_context(|| format!("Can't create Kaldi script file : {:?}", scp_path.as_ref()))?;
+        let ark_path = ark_path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| format_err!("Invalid archive path : {:?}", ark_path.as_ref()))?;
+        writer.scp = Some((ark_path.to_string(), BufWriter::new(scp_file)));
+        Ok(writer)
+    }
+}
+
+impl<F: Write> ArkWriter<F> {
+    /// Writes an archive into any writer, e.g the standard output.
+    pub fn new(writer: F) -> Self {
+        Self {
+            writer,
+            offset: 0,
+            scp: None,
+        }
+    }
+
+    /// Appends an FST to the archive. The key must be non-empty and must not contain whitespaces.
+    pub fn write<W: SerializableSemiring + 'static>(
+        &mut self,
+        key: &str,
+        fst: &VectorFst<W>,
+    ) -> Result<()> {
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            bail!("Invalid key for a Kaldi archive : {:?}", key);
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(key.as_bytes());
+        data.push(b' ');
+        let fst_offset = self.offset + data.len() as u64;
+        data.extend_from_slice(KALDI_BINARY_HEADER);
+        fst.write_binary(&mut data)?;
+
+        self.writer.write_all(&data)?;
+        self.offset += data.len() as u64;
+        if let Some((ark_path, scp_writer)) = &mut self.scp {
+            writeln!(scp_writer, "{} {}:{}", key, ark_path, fst_offset)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the archive and the script file.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        if let Some((_, scp_writer)) = &mut self.scp {
+            scp_writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Write> Drop for ArkWriter<F> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::fst_traits::MutableFst;
+    use crate::semirings::{CompactLatticeWeight, LatticeWeight, Semiring, TropicalWeight};
+    use crate::Tr;
+
+    fn lattice(n: usize) -> Result<VectorFst<CompactLatticeWeight>> {
+        let mut fst = VectorFst::new();
+        let mut prev = fst.add_state();
+        fst.set_start(prev)?;
+        for i in 0..n {
+            let next = fst.add_state();
+            let w = CompactLatticeWeight::new((LatticeWeight::from((i as f32, 1.0)), vec![i, i]));
+            fst.add_tr(prev, Tr::new(i + 1, i + 1, w, next))?;
+            prev = next;
+        }
+        fst.set_final(prev, CompactLatticeWeight::one())?;
+        Ok(fst)
+    }
+
+    #[test]
+    fn test_ark_scp_roundtrip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let ark_path = dir.path().join("lat.ark");
+        let scp_path = dir.path().join("lat.scp");
+
+        // Large enough to span several chunks.
+        let fsts = vec![
+            ("utt1".to_string(), lattice(3)?),
+            ("utt2".to_string(), lattice(5000)?),
+            ("utt3".to_string(), lattice(0)?),
+        ];
+        {
+            let mut writer = ArkWriter::create_with_scp(&ark_path, &scp_path)?;
+            for (key, fst) in fsts.iter() {
+                writer.write(key, fst)?;
+            }
+        }
+
+        let from_ark =
+            ArkReader::<CompactLatticeWeight, _>::open(&ark_path)?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(from_ark, fsts);
+
+        let from_scp =
+            ScpReader::<CompactLatticeWeight>::open(&scp_path)?.collect::<Result<Vec<_>>>()?;
+        assert_eq!(from_scp, fsts);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ark_reader_errors() -> Result<()> {
+        let mut data = Vec::new();
+        {
+            let mut writer = ArkWriter::new(&mut data);
+            let mut fst = VectorFst::<TropicalWeight>::new();
+            let s = fst.add_state();
+            fst.set_start(s)?;
+            fst.set_final(s, TropicalWeight::one())?;
+            writer.write("utt1", &fst)?;
+            assert!(writer.write("utt 2", &fst).is_err());
+        }
+        // Truncated archive.
+        data.extend_from_slice(b"utt2 \0B");
+        let entries: Vec<_> = ArkReader::<TropicalWeight, _>::new(data.as_slice()).collect();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_ok());
+        assert!(entries[1].is_err());
+
+        // Wrong semiring.
+        let mut entries = ArkReader::<LatticeWeight, _>::new(data.as_slice());
+        assert!(entries.next().unwrap().is_err());
+        assert!(entries.next().is_none());
+        Ok(())
+    }
+
+    /// Reader counting the number of bytes read.
+    struct CountingReader<R: Read> {
+        reader: R,
+        count: Rc<Cell<usize>>,
+    }
+
+    impl<R: Read> Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.reader.read(buf)?;
+            self.count.set(self.count.get() + read);
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn test_ark_reader_corrupt_entry() -> Result<()> {
+        let mut data = Vec::new();
+        let mut writer = ArkWriter::new(&mut data);
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        let s = fst.add_state();
+        fst.set_start(s)?;
+        fst.set_final(s, TropicalWeight::one())?;
+        writer.write("utt1", &fst)?;
+        drop(writer);
+        // Corrupt the magic number of the FST header.
+        data[7] ^= 0xff;
+
+        // The error is reported without reading the rest of the input.
+        let count = Rc::new(Cell::new(0));
+        let reader = CountingReader {
+            reader: data.as_slice().chain(std::io::repeat(0).take(1 << 24)),
+            count: Rc::clone(&count),
+        };
+        let mut entries = ArkReader::<TropicalWeight, _>::new(reader);
+        assert!(entries.next().unwrap().is_err());
+        assert!(entries.next().is_none());
+        assert_eq!(count.get(), CHUNK_SIZE);
+        Ok(())
+    }
+}
@@ -156,6 +156,12 @@ pub mod fst_impls;
 pub mod semirings;
 
 mod drawing_config;
+/// Readers and writers for the Kaldi table formats (`ark` and `scp` files) of FSTs and lattices.
+pub mod kaldi_table;
+/// Compilation of regular expressions into wFSTs.
+pub mod regex;
+/// High-level API to apply rewrite rules to strings.
+pub mod rewrite;
 /// Implementation of a successful path inside a wFST.
 mod fst_path;
 mod parsers;
@@ -21,6 +21,12 @@ pub(crate) fn write_bin_i64<F: Write>(file: &mut F, i: i64) -> Result<()> {
     file.write_all(&i.to_le_bytes()).map_err(|e| e.into())
 }
 
+#[inline]
+pub(crate) fn write_bin_f64<F: Write>(file: &mut F, i: f64) -> Result<()> {
+    file.write_all(&i.to_bits().to_le_bytes())
+        .map_err(|e| e.into())
+}
+
 #[inline]
 pub(crate) fn write_bin_f32<F: Write>(file: &mut F, i: f32) -> Result<()> {
     file.write_all(&i.to_bits().to_le_bytes())
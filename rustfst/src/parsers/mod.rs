@@ -1,5 +1,7 @@
 pub(crate) mod bin_fst;
 pub(crate) mod bin_symt;
 pub mod nom_utils;
+#[cfg(feature = "serde")]
+pub(crate) mod serde_utils;
 pub mod text_fst;
 pub(crate) mod text_symt;
@@ -0,0 +1,286 @@
+use std::f64;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{SerializeSeq, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{ExpandedFst, Fst, MutableFst};
+use crate::semirings::Semiring;
+use crate::{StateId, SymbolTable, Tr, Trs};
+
+/// Serializes a float as a JSON number or as one of the strings
+/// `"Infinity"`, `"-Infinity"` and `"NaN"` which can't be represented as numbers.
+pub(crate) fn serialize_f32<S: Serializer>(value: f32, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.is_finite() {
+        serializer.serialize_f32(value)
+    } else {
+        serialize_non_finite(f64::from(value), serializer)
+    }
+}
+
+/// Same as `serialize_f32` with double precision.
+pub(crate) fn serialize_f64<S: Serializer>(value: f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.is_finite() {
+        serializer.serialize_f64(value)
+    } else {
+        serialize_non_finite(value, serializer)
+    }
+}
+
+fn serialize_non_finite<S: Serializer>(value: f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.is_nan() {
+        serializer.serialize_str("NaN")
+    } else if value.is_sign_positive() {
+        serializer.serialize_str("Infinity")
+    } else {
+        serializer.serialize_str("-Infinity")
+    }
+}
+
+struct FloatVisitor;
+
+impl<'de> Visitor<'de> for FloatVisitor {
+    type Value = f64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a float or one of \"Infinity\", \"-Infinity\" and \"NaN\"")
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(v as f64)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        match v {
+            "Infinity" | "inf" => Ok(f64::INFINITY),
+            "-Infinity" | "-inf" => Ok(f64::NEG_INFINITY),
+            "NaN" | "nan" => Ok(f64::NAN),
+            _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+/// Inverse of `serialize_f32`.
+pub(crate) fn deserialize_f32<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    deserialize_f64(deserializer).map(|v| v as f32)
+}
+
+/// Inverse of `serialize_f64`.
+pub(crate) fn deserialize_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserializer.deserialize_any(FloatVisitor)
+}
+
+/// Serialized representation of a state : its final weight and its outgoing transitions.
+struct SerdeState<'a, W: Semiring, F: Fst<W>> {
+    fst: &'a F,
+    state: StateId,
+    w: PhantomData<W>,
+}
+
+impl<'a, W: Semiring + Serialize, F: Fst<W>> Serialize for SerdeState<'a, W, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let final_weight = unsafe { self.fst.final_weight_unchecked(self.state) };
+        let trs = unsafe { self.fst.get_trs_unchecked(self.state) };
+        let mut s = serializer.serialize_struct("State", 2)?;
+        s.serialize_field("final_weight", &final_weight)?;
+        s.serialize_field("trs", trs.trs())?;
+        s.end()
+    }
+}
+
+struct SerdeStates<'a, W: Semiring, F: ExpandedFst<W>> {
+    fst: &'a F,
+    w: PhantomData<W>,
+}
+
+impl<'a, W: Semiring + Serialize, F: ExpandedFst<W>> Serialize for SerdeStates<'a, W, F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.fst.num_states()))?;
+        for state in 0..self.fst.num_states() {
+            seq.serialize_element(&SerdeState {
+                fst: self.fst,
+                state,
+                w: PhantomData,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+/// Serializes any `ExpandedFst` following the schema described in the documentation of `VectorFst`.
+pub(crate) fn serialize_fst<W, F, S>(fst: &F, serializer: S) -> Result<S::Ok, S::Error>
+where
+    W: Semiring + Serialize,
+    F: ExpandedFst<W>,
+    S: Serializer,
+{
+    let mut s = serializer.serialize_struct("Fst", 4)?;
+    s.serialize_field("start", &fst.start())?;
+    s.serialize_field(
+        "states",
+        &SerdeStates {
+            fst,
+            w: PhantomData,
+        },
+    )?;
+    s.serialize_field("input_symbols", &fst.input_symbols().map(|s| s.as_ref()))?;
+    s.serialize_field("output_symbols", &fst.output_symbols().map(|s| s.as_ref()))?;
+    s.end()
+}
+
+#[derive(Deserialize)]
+struct ParsedSerdeState<W> {
+    final_weight: Option<W>,
+    #[serde(default = "Vec::new")]
+    trs: Vec<Tr<W>>,
+}
+
+#[derive(Deserialize)]
+struct ParsedSerdeFst<W> {
+    start: Option<StateId>,
+    states: Vec<ParsedSerdeState<W>>,
+    input_symbols: Option<SymbolTable>,
+    output_symbols: Option<SymbolTable>,
+}
+
+/// Deserializes a `VectorFst` following the schema described in the documentation of `VectorFst`.
+pub(crate) fn deserialize_vector_fst<'de, W, D>(deserializer: D) -> Result<VectorFst<W>, D::Error>
+where
+    W: Semiring + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let parsed_fst = ParsedSerdeFst::<W>::deserialize(deserializer)?;
+    let num_states = parsed_fst.states.len();
+    let check_state = |state: StateId| {
+        if state < num_states {
+            Ok(())
+        } else {
+            Err(de::Error::custom(format!(
+                "State {} doesn't exist, the FST has {} states",
+                state, num_states
+            )))
+        }
+    };
+
+    let mut fst = VectorFst::new();
+    fst.add_states(num_states);
+    if let Some(start) = parsed_fst.start {
+        check_state(start)?;
+        unsafe { fst.set_start_unchecked(start) };
+    }
+    for (state, parsed_state) in parsed_fst.states.into_iter().enumerate() {
+        if let Some(final_weight) = parsed_state.final_weight {
+            unsafe { fst.set_final_unchecked(state, final_weight) };
+        }
+        for tr in parsed_state.trs {
+            check_state(tr.nextstate)?;
+            unsafe { fst.add_tr_unchecked(state, tr) };
+        }
+    }
+    if let Some(isymt) = parsed_fst.input_symbols {
+        fst.set_input_symbols(isymt.into());
+    }
+    if let Some(osymt) = parsed_fst.output_symbols {
+        fst.set_output_symbols(osymt.into());
+    }
+    Ok(fst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use anyhow::Result;
+
+    use crate::fst_impls::ConstFst;
+    use crate::semirings::{GallicWeightLeft, ProductWeight, StringWeightLeft, TropicalWeight};
+
+    fn build_fst() -> Result<VectorFst<TropicalWeight>> {
+        let mut fst = VectorFst::<TropicalWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 2, 0.5, s1))?;
+        fst.add_tr(s0, Tr::new(2, 1, f32::INFINITY, s1))?;
+        fst.set_final(s1, 1.5)?;
+
+        let mut symt = SymbolTable::new();
+        symt.add_symbols(vec!["a", "b"]);
+        fst.set_input_symbols(Arc::new(symt));
+        Ok(fst)
+    }
+
+    #[test]
+    fn test_serde_vector_fst_json() -> Result<()> {
+        let fst = build_fst()?;
+        let json = serde_json::to_value(&fst)?;
+        let expected = serde_json::json!({
+            "start": 0,
+            "states": [
+                {
+                    "final_weight": null,
+                    "trs": [
+                        {"ilabel": 1, "olabel": 2, "weight": 0.5, "nextstate": 1},
+                        {"ilabel": 2, "olabel": 1, "weight": "Infinity", "nextstate": 1}
+                    ]
+                },
+                {"final_weight": 1.5, "trs": []}
+            ],
+            "input_symbols": [[0, "<eps>"], [1, "a"], [2, "b"]],
+            "output_symbols": null
+        });
+        assert_eq!(json, expected);
+
+        let deserialized: VectorFst<TropicalWeight> = serde_json::from_value(json)?;
+        assert_eq!(deserialized, fst);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_const_fst_json() -> Result<()> {
+        let fst: ConstFst<TropicalWeight> = build_fst()?.into();
+        let json = serde_json::to_string(&fst)?;
+        let deserialized: ConstFst<TropicalWeight> = serde_json::from_str(&json)?;
+        assert_eq!(deserialized, fst);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_fst_invalid_state() {
+        let json = r#"{"start": 0, "states": [{"final_weight": null, "trs": [{"ilabel": 1, "olabel": 1, "weight": 0.0, "nextstate": 3}]}]}"#;
+        assert!(serde_json::from_str::<VectorFst<TropicalWeight>>(json).is_err());
+    }
+
+    #[test]
+    fn test_serde_weights_json() -> Result<()> {
+        let w = GallicWeightLeft::from((vec![1, 2], TropicalWeight::new(0.5)));
+        let json = serde_json::to_value(&w)?;
+        assert_eq!(json, serde_json::json!([[1, 2], 0.5]));
+        assert_eq!(
+            serde_json::from_value::<GallicWeightLeft<TropicalWeight>>(json)?,
+            w
+        );
+
+        let w = ProductWeight::new((StringWeightLeft::zero(), TropicalWeight::zero()));
+        let json = serde_json::to_value(&w)?;
+        assert_eq!(json, serde_json::json!([null, "Infinity"]));
+        assert_eq!(
+            serde_json::from_value::<ProductWeight<StringWeightLeft, TropicalWeight>>(json)?,
+            w
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::algorithms::closure::{closure, ClosureType};
+use crate::algorithms::concat::concat;
+use crate::algorithms::determinize::{determinize, DeterminizeType};
+use crate::algorithms::minimize;
+use crate::algorithms::rm_epsilon::rm_epsilon;
+use crate::algorithms::union::union;
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{CoreFst, ExpandedFst, Fst, MutableFst};
+use crate::regex::regex_parser::{RegexAst, RegexParser};
+use crate::semirings::{SerializableSemiring, WeaklyDivisibleSemiring, WeightQuantize};
+use crate::utils::{acceptor, TokenType};
+use crate::{Label, Tr};
+
+/// Options for the compilation of a regular expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegexConfig {
+    /// How the characters of the regex are converted to labels.
+    pub token_type: TokenType,
+    /// Alphabet used by `.` and negated character classes. If `None`, the default alphabet
+    /// of the token type is used : all the bytes for `Byte`, the ASCII code points for `Utf8`
+    /// and all the non-epsilon labels of the `SymbolTable`.
+    pub sigma: Option<Vec<Label>>,
+    /// Remove the epsilon transitions of the compiled FST.
+    pub rm_epsilon: bool,
+    /// Determinize the compiled FST.
+    pub determinize: bool,
+    /// Minimize the compiled FST.
+    pub minimize: bool,
+}
+
+impl Default for RegexConfig {
+    fn default() -> Self {
+        Self {
+            token_type: TokenType::Utf8,
+            sigma: None,
+            rm_epsilon: false,
+            determinize: false,
+            minimize: false,
+        }
+    }
+}
+
+impl RegexConfig {
+    pub fn new(token_type: TokenType) -> Self {
+        Self {
+            token_type,
+            ..Default::default()
+        }
+    }
+
+    /// Enables epsilon removal, determinization and minimization.
+    pub fn optimize(self) -> Self {
+        Self {
+            rm_epsilon: true,
+            determinize: true,
+            minimize: true,
+            ..self
+        }
+    }
+}
+
+/// Compiles a regular expression into an acceptor using the Thompson construction.
+///
+/// The supported syntax is :
+/// - `ab` : concatenation.
+/// - `a|b` : alternation. Each alternative can be followed by a weight between angle
+///   brackets, e.g. `a<1.5>|b<2.0>`, which is parsed with `parse_text` of the semiring.
+/// - `a*`, `a+`, `a?` : closure star, closure plus and optional.
+/// - `a{m}`, `a{m,}`, `a{m,n}`, `a{,n}` : bounded repetitions.
+/// - `(...)` : grouping.
+/// - `[abc]`, `[a-z]`, `[^abc]` : character classes and negated character classes.
+/// - `.` : any label of the alphabet.
+/// - `\d`, `\w`, `\s` : digits, word characters and whitespaces. `\n`, `\t` and `\r` are
+///   the usual control characters and any other escaped character is taken literally.
+/// - `` `name` `` : a multi-character symbol of the `SymbolTable`.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::regex::{compile_regex, RegexConfig};
+/// # use rustfst::semirings::TropicalWeight;
+/// # use rustfst::utils::TokenType;
+/// # fn main() -> Result<()> {
+/// let config = RegexConfig::new(TokenType::Byte).optimize();
+/// let fst: VectorFst<TropicalWeight> = compile_regex("ab{1,2}|c", &config)?;
+///
+/// let mut paths: Vec<_> = fst.paths_iter().map(|p| p.ilabels).collect();
+/// paths.sort();
+/// assert_eq!(paths, vec![vec![97, 98], vec![97, 98, 98], vec![99]]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn compile_regex<W>(regex: &str, config: &RegexConfig) -> Result<VectorFst<W>>
+where
+    W: SerializableSemiring + WeaklyDivisibleSemiring + WeightQuantize,
+{
+    let sigma = config
+        .sigma
+        .clone()
+        .unwrap_or_else(|| config.token_type.sigma());
+    let ast = RegexParser::new(regex, &config.token_type, &sigma).parse()?;
+    let mut fst = compile_ast(&ast)?;
+
+    if config.rm_epsilon {
+        rm_epsilon(&mut fst)?;
+    }
+    if config.determinize {
+        fst = determinize(Arc::new(fst), DeterminizeType::DeterminizeFunctional)?;
+    }
+    if config.minimize {
+        minimize(&mut fst, true)?;
+    }
+
+    if let Some(symt) = config.token_type.symbol_table() {
+        fst.set_input_symbols(Arc::clone(&symt));
+        fst.set_output_symbols(symt);
+    }
+    Ok(fst)
+}
+
+fn compile_ast<W: SerializableSemiring>(ast: &RegexAst<W>) -> Result<VectorFst<W>> {
+    let fst = match ast {
+        RegexAst::Epsilon => acceptor(&[], W::one()),
+        RegexAst::Labels(labels) => acceptor(labels, W::one()),
+        RegexAst::Class(labels) => {
+            let mut fst = VectorFst::new();
+            let s0 = fst.add_state();
+            let s1 = fst.add_state();
+            fst.set_start(s0)?;
+            fst.set_final(s1, W::one())?;
+            for label in labels {
+                fst.add_tr(s0, Tr::new(*label, *label, W::one(), s1))?;
+            }
+            fst
+        }
+        RegexAst::Concat(items) => {
+            let mut fst = acceptor(&[], W::one());
+            for item in items {
+                concat(&mut fst, &compile_ast(item)?)?;
+            }
+            fst
+        }
+        RegexAst::Union(alternatives) => {
+            let mut fst = VectorFst::new();
+            for (item, weight) in alternatives {
+                let mut fst_item = compile_ast(item)?;
+                if let Some(weight) = weight {
+                    for s in 0..fst_item.num_states() {
+                        if let Some(final_weight) = fst_item.final_weight(s)? {
+                            fst_item.set_final(s, final_weight.times(weight)?)?;
+                        }
+                    }
+                }
+                union(&mut fst, &fst_item)?;
+            }
+            fst
+        }
+        RegexAst::Repeat { ast, min, max } => {
+            let fst_item = compile_ast(ast)?;
+            let mut fst = acceptor(&[], W::one());
+            for _ in 0..*min {
+                concat(&mut fst, &fst_item)?;
+            }
+            match max {
+                None => {
+                    let mut fst_star = fst_item;
+                    closure(&mut fst_star, ClosureType::ClosureStar);
+                    concat(&mut fst, &fst_star)?;
+                }
+                Some(max) => {
+                    let mut fst_opt = fst_item;
+                    union(&mut fst_opt, &acceptor::<W, VectorFst<W>>(&[], W::one()))?;
+                    for _ in *min..*max {
+                        concat(&mut fst, &fst_opt)?;
+                    }
+                }
+            }
+            fst
+        }
+    };
+    Ok(fst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_traits::PathsIterator;
+    use crate::semirings::{Semiring, TropicalWeight};
+    use crate::SymbolTable;
+
+    fn paths(fst: &VectorFst<TropicalWeight>) -> Vec<(String, TropicalWeight)> {
+        let mut paths: Vec<_> = fst
+            .paths_iter()
+            .map(|p| {
+                let s: String = p.ilabels.iter().map(|l| *l as u8 as char).collect();
+                (s, p.weight)
+            })
+            .collect();
+        paths.sort_by(|a, b| a.0.cmp(&b.0));
+        paths
+    }
+
+    fn strings(regex: &str) -> Result<Vec<String>> {
+        let config = RegexConfig::new(TokenType::Byte).optimize();
+        let fst = compile_regex(regex, &config)?;
+        Ok(paths(&fst).into_iter().map(|p| p.0).collect())
+    }
+
+    #[test]
+    fn test_compile_regex_finite() -> Result<()> {
+        assert_eq!(strings("abc")?, vec!["abc"]);
+        assert_eq!(strings("a|bc|")?, vec!["", "a", "bc"]);
+        assert_eq!(strings("a(b|c)?d")?, vec!["abd", "acd", "ad"]);
+        assert_eq!(strings("[a-c]x")?, vec!["ax", "bx", "cx"]);
+        assert_eq!(strings("a{2}")?, vec!["aa"]);
+        assert_eq!(strings("a{1,3}")?, vec!["a", "aa", "aaa"]);
+        assert_eq!(strings("a{,1}")?, vec!["", "a"]);
+        assert_eq!(strings("\\(\\d")?.len(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_closure() -> Result<()> {
+        let config = RegexConfig::new(TokenType::Byte).optimize();
+        let fst: VectorFst<TropicalWeight> = compile_regex("ab*", &config)?;
+        assert_eq!(fst.num_states(), 2);
+        let fst: VectorFst<TropicalWeight> = compile_regex("(ab){2,}", &config)?;
+        assert_eq!(fst.num_states(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_negated_class() -> Result<()> {
+        let config = RegexConfig {
+            sigma: Some(vec![97, 98, 99]),
+            ..RegexConfig::new(TokenType::Byte).optimize()
+        };
+        let fst: VectorFst<TropicalWeight> = compile_regex("[^b].", &config)?;
+        assert_eq!(paths(&fst).len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_weights() -> Result<()> {
+        let config = RegexConfig::new(TokenType::Byte);
+        let fst = compile_regex("a<1.5>|b<2>|c", &config)?;
+        assert_eq!(
+            paths(&fst),
+            vec![
+                ("a".to_string(), TropicalWeight::new(1.5)),
+                ("b".to_string(), TropicalWeight::new(2.0)),
+                ("c".to_string(), TropicalWeight::one()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_utf8() -> Result<()> {
+        let fst: VectorFst<TropicalWeight> =
+            compile_regex("é", &RegexConfig::new(TokenType::Byte))?;
+        assert_eq!(fst.paths_iter().next().unwrap().ilabels, vec![0xc3, 0xa9]);
+        let fst: VectorFst<TropicalWeight> =
+            compile_regex("é", &RegexConfig::new(TokenType::Utf8))?;
+        assert_eq!(fst.paths_iter().next().unwrap().ilabels, vec![0xe9]);
+        assert!(
+            compile_regex::<TropicalWeight>("[é]", &RegexConfig::new(TokenType::Byte)).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_symbol_table() -> Result<()> {
+        let mut symt = SymbolTable::new();
+        symt.add_symbols(vec!["a", "b", "hello"]);
+        let symt = Arc::new(symt);
+        let config = RegexConfig::new(TokenType::SymbolTable(Arc::clone(&symt))).optimize();
+        let fst: VectorFst<TropicalWeight> = compile_regex("a`hello`[^a]", &config)?;
+        let mut labels: Vec<_> = fst.paths_iter().map(|p| p.ilabels).collect();
+        labels.sort();
+        assert_eq!(labels, vec![vec![1, 3, 2], vec![1, 3, 3]]);
+        assert_eq!(fst.input_symbols(), Some(&symt));
+        assert!(compile_regex::<TropicalWeight>("c", &config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_regex_errors() {
+        let config = RegexConfig::default();
+        for regex in &["(a", "a)", "*a", "a{3,2}", "[a", "a<1.0", "a<x>", "`ab`"] {
+            assert!(
+                compile_regex::<TropicalWeight>(regex, &config).is_err(),
+                "{}",
+                regex
+            );
+        }
+    }
+}
@@ -0,0 +1,4 @@
+mod compile_regex;
+mod regex_parser;
+
+pub use self::compile_regex::{compile_regex, RegexConfig};
@@ -0,0 +1,320 @@
+use anyhow::{bail, format_err, Result};
+
+use crate::semirings::SerializableSemiring;
+use crate::utils::TokenType;
+use crate::Label;
+
+/// Abstract syntax tree of a regular expression. Characters are already converted to labels.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RegexAst<W> {
+    /// Matches the empty string.
+    Epsilon,
+    /// Matches a sequence of labels.
+    Labels(Vec<Label>),
+    /// Matches exactly one label among a set.
+    Class(Vec<Label>),
+    Concat(Vec<RegexAst<W>>),
+    /// Alternatives with their optional weight.
+    Union(Vec<(RegexAst<W>, Option<W>)>),
+    Repeat {
+        ast: Box<RegexAst<W>>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+/// Recursive descent parser for the regex syntax described in `compile_regex`.
+pub(crate) struct RegexParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    token_type: &'a TokenType,
+    sigma: &'a [Label],
+}
+
+impl<'a> RegexParser<'a> {
+    pub(crate) fn new(regex: &str, token_type: &'a TokenType, sigma: &'a [Label]) -> Self {
+        Self {
+            chars: regex.chars().collect(),
+            pos: 0,
+            token_type,
+            sigma,
+        }
+    }
+
+    pub(crate) fn parse<W: SerializableSemiring>(mut self) -> Result<RegexAst<W>> {
+        let ast = self.parse_union()?;
+        if let Some(c) = self.peek() {
+            bail!("Unexpected character `{}` at position {}", c, self.pos);
+        }
+        Ok(ast)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Result<char> {
+        let c = self
+            .peek()
+            .ok_or_else(|| format_err!("Unexpected end of regex"))?;
+        self.pos += 1;
+        Ok(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        let c = self.next()?;
+        if c != expected {
+            bail!(
+                "Expected `{}` at position {}, found `{}`",
+                expected,
+                self.pos - 1,
+                c
+            );
+        }
+        Ok(())
+    }
+
+    fn parse_union<W: SerializableSemiring>(&mut self) -> Result<RegexAst<W>> {
+        let mut alternatives = vec![];
+        loop {
+            let ast = self.parse_concat()?;
+            let weight = if self.peek() == Some('<') {
+                Some(self.parse_weight()?)
+            } else {
+                None
+            };
+            alternatives.push((ast, weight));
+            if self.peek() == Some('|') {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if alternatives.len() == 1 && alternatives[0].1.is_none() {
+            Ok(alternatives.pop().unwrap().0)
+        } else {
+            Ok(RegexAst::Union(alternatives))
+        }
+    }
+
+    fn parse_weight<W: SerializableSemiring>(&mut self) -> Result<W> {
+        self.expect('<')?;
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c != '>') {
+            self.pos += 1;
+        }
+        let weight_str: String = self.chars[start..self.pos].iter().collect();
+        self.expect('>')?;
+        let (rest, weight) = W::parse_text(weight_str.trim())
+            .map_err(|_| format_err!("Can't parse weight `{}`", weight_str))?;
+        if !rest.is_empty() {
+            bail!("Can't parse weight `{}`", weight_str);
+        }
+        Ok(weight)
+    }
+
+    fn parse_concat<W: SerializableSemiring>(&mut self) -> Result<RegexAst<W>> {
+        let mut items = vec![];
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' || c == '<' {
+                break;
+            }
+            items.push(self.parse_repeat()?);
+        }
+        match items.len() {
+            0 => Ok(RegexAst::Epsilon),
+            1 => Ok(items.pop().unwrap()),
+            _ => Ok(RegexAst::Concat(items)),
+        }
+    }
+
+    fn parse_repeat<W: SerializableSemiring>(&mut self) -> Result<RegexAst<W>> {
+        let mut ast = self.parse_atom()?;
+        while let Some(c) = self.peek() {
+            let (min, max) = match c {
+                '*' => (0, None),
+                '+' => (1, None),
+                '?' => (0, Some(1)),
+                '{' => {
+                    self.pos += 1;
+                    self.parse_bounds()?
+                }
+                _ => break,
+            };
+            if c != '{' {
+                self.pos += 1;
+            }
+            ast = RegexAst::Repeat {
+                ast: Box::new(ast),
+                min,
+                max,
+            };
+        }
+        Ok(ast)
+    }
+
+    /// Parses `m}`, `m,}`, `m,n}` or `,n}` after an opening brace.
+    fn parse_bounds(&mut self) -> Result<(usize, Option<usize>)> {
+        let min = self.parse_number()?;
+        let max = if self.peek() == Some(',') {
+            self.pos += 1;
+            self.parse_number()?
+        } else {
+            Some(min.ok_or_else(|| format_err!("Missing bound at position {}", self.pos))?)
+        };
+        self.expect('}')?;
+        let min = min.unwrap_or(0);
+        if let Some(max) = max {
+            if max < min {
+                bail!("Invalid repetition bounds {{{},{}}}", min, max);
+            }
+        }
+        Ok((min, max))
+    }
+
+    fn parse_number(&mut self) -> Result<Option<usize>> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        let number: String = self.chars[start..self.pos].iter().collect();
+        Ok(Some(number.parse()?))
+    }
+
+    fn parse_atom<W: SerializableSemiring>(&mut self) -> Result<RegexAst<W>> {
+        let c = self.next()?;
+        match c {
+            '(' => {
+                let ast = self.parse_union()?;
+                self.expect(')')?;
+                Ok(ast)
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(RegexAst::Class(self.sigma.to_vec())),
+            '`' => {
+                let start = self.pos;
+                while matches!(self.peek(), Some(c) if c != '`') {
+                    self.pos += 1;
+                }
+                let symbol: String = self.chars[start..self.pos].iter().collect();
+                self.expect('`')?;
+                Ok(RegexAst::Labels(vec![self
+                    .token_type
+                    .multichar_symbol_to_label(&symbol)?]))
+            }
+            '\\' => {
+                let e = self.next()?;
+                match escape_class(e) {
+                    Some(chars) => Ok(RegexAst::Class(self.chars_to_labels(chars)?)),
+                    None => Ok(RegexAst::Labels(
+                        self.token_type.char_to_labels(escape_char(e))?,
+                    )),
+                }
+            }
+            '*' | '+' | '?' | '{' | '}' | ')' | ']' | '|' | '>' => {
+                bail!("Unexpected character `{}` at position {}", c, self.pos - 1)
+            }
+            c => Ok(RegexAst::Labels(self.token_type.char_to_labels(c)?)),
+        }
+    }
+
+    /// Parses the content of a character class after the opening bracket.
+    fn parse_class<W>(&mut self) -> Result<RegexAst<W>> {
+        let negated = if self.peek() == Some('^') {
+            self.pos += 1;
+            true
+        } else {
+            false
+        };
+        let mut chars = vec![];
+        let mut first = true;
+        loop {
+            let mut c = self.next()?;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            if c == '\\' {
+                let e = self.next()?;
+                if let Some(class_chars) = escape_class(e) {
+                    chars.extend(class_chars);
+                    continue;
+                }
+                c = escape_char(e);
+            }
+            let is_range = self.peek() == Some('-')
+                && matches!(self.chars.get(self.pos + 1), Some(c) if *c != ']');
+            if is_range {
+                self.pos += 1;
+                let mut end = self.next()?;
+                if end == '\\' {
+                    end = escape_char(self.next()?);
+                }
+                if end < c {
+                    bail!("Invalid range `{}-{}` in character class", c, end);
+                }
+                chars.extend(c..=end);
+            } else {
+                chars.push(c);
+            }
+        }
+
+        let mut labels = self.chars_to_labels(chars)?;
+        if negated {
+            labels = self
+                .sigma
+                .iter()
+                .filter(|l| labels.binary_search(l).is_err())
+                .cloned()
+                .collect();
+        }
+        Ok(RegexAst::Class(labels))
+    }
+
+    /// Sorted and deduplicated labels of a set of characters. Each character must be mapped
+    /// to a single label.
+    fn chars_to_labels<I: IntoIterator<Item = char>>(&self, chars: I) -> Result<Vec<Label>> {
+        let mut labels = vec![];
+        for c in chars {
+            let mut c_labels = self.token_type.char_to_labels(c)?;
+            if c_labels.len() != 1 {
+                bail!(
+                    "Character `{}` can't be used in a character class as it is made of {} tokens",
+                    c,
+                    c_labels.len()
+                );
+            }
+            labels.push(c_labels.pop().unwrap());
+        }
+        labels.sort();
+        labels.dedup();
+        Ok(labels)
+    }
+}
+
+fn escape_class(c: char) -> Option<Vec<char>> {
+    match c {
+        'd' => Some(('0'..='9').collect()),
+        'w' => Some(
+            ('a'..='z')
+                .chain('A'..='Z')
+                .chain('0'..='9')
+                .chain(Some('_'))
+                .collect(),
+        ),
+        's' => Some(vec![' ', '\t', '\n', '\r']),
+        _ => None,
+    }
+}
+
+fn escape_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
@@ -0,0 +1,305 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use crate::algorithms::compose::compose;
+use crate::algorithms::determinize::{determinize, DeterminizeType};
+use crate::algorithms::queues::natural_less;
+use crate::algorithms::rm_epsilon::rm_epsilon;
+use crate::algorithms::tr_compares::{ilabel_compare, olabel_compare};
+use crate::algorithms::{project, shortest_path, tr_sort, ProjectType};
+use crate::fst_impls::VectorFst;
+use crate::fst_path::FstPath;
+use crate::fst_properties::FstProperties;
+use crate::fst_traits::{CoreFst, ExpandedFst, PathsIterator};
+use crate::semirings::{Semiring, WeaklyDivisibleSemiring, WeightQuantize};
+use crate::utils::{acceptor, compile_string, print_string, TokenType};
+
+/// Input of the rewrite functions that can be turned into a linear acceptor.
+pub trait RewriteInput<W: Semiring> {
+    fn to_acceptor(&self, token_type: &TokenType) -> Result<VectorFst<W>>;
+}
+
+impl<W: Semiring> RewriteInput<W> for str {
+    fn to_acceptor(&self, token_type: &TokenType) -> Result<VectorFst<W>> {
+        compile_string(self, token_type, W::one())
+    }
+}
+
+impl<W: Semiring> RewriteInput<W> for String {
+    fn to_acceptor(&self, token_type: &TokenType) -> Result<VectorFst<W>> {
+        self.as_str().to_acceptor(token_type)
+    }
+}
+
+/// The output labels of the path are used, weighted by the weight of the path. This allows
+/// to chain rewrites.
+impl<W: Semiring> RewriteInput<W> for FstPath<W> {
+    fn to_acceptor(&self, _token_type: &TokenType) -> Result<VectorFst<W>> {
+        Ok(acceptor(&self.olabels, self.weight.clone()))
+    }
+}
+
+/// Builds the lattice of all the rewrites of `input` by `rule` : the linear acceptor of the
+/// input is composed with the rule, projected on the output labels and epsilons are removed.
+///
+/// Returns an error if the rule doesn't accept the input.
+pub fn rewrite_lattice<W, F, I>(
+    input: &I,
+    rule: &Arc<F>,
+    token_type: &TokenType,
+) -> Result<VectorFst<W>>
+where
+    W: Semiring,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+{
+    match accepted_lattice(input, rule, token_type)? {
+        Some(lattice) => Ok(lattice),
+        None => bail!("Rewrite failure : the rule doesn't accept the input"),
+    }
+}
+
+/// Same as `rewrite_lattice` but returns `None` if the rule doesn't accept the input.
+fn accepted_lattice<W, F, I>(
+    input: &I,
+    rule: &Arc<F>,
+    token_type: &TokenType,
+) -> Result<Option<VectorFst<W>>>
+where
+    W: Semiring,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+{
+    let mut input_fst = input.to_acceptor(token_type)?;
+    tr_sort(&mut input_fst, olabel_compare);
+    let mut lattice: VectorFst<W> = compose(Arc::new(input_fst), Arc::clone(rule))?;
+    if lattice.start().is_none() {
+        return Ok(None);
+    }
+    project(&mut lattice, ProjectType::ProjectOutput);
+    rm_epsilon(&mut lattice)?;
+    Ok(Some(lattice))
+}
+
+/// Returns the rewrite of `input` by `rule` with the best weight.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use std::sync::Arc;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::rewrite::top_rewrite;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::{string_map, TokenType};
+/// # fn main() -> Result<()> {
+/// let rule: VectorFst<TropicalWeight> = string_map(
+///     vec![
+///         ("cat", "chat", TropicalWeight::new(1.0)),
+///         ("cat", "matou", TropicalWeight::new(2.0)),
+///     ],
+///     &TokenType::Byte,
+///     &TokenType::Byte,
+///     false,
+/// )?;
+/// let rule = Arc::new(rule);
+///
+/// assert_eq!(top_rewrite("cat", &rule, &TokenType::Byte)?, "chat");
+/// assert!(top_rewrite("dog", &rule, &TokenType::Byte).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn top_rewrite<W, F, I>(input: &I, rule: &Arc<F>, token_type: &TokenType) -> Result<String>
+where
+    W: Semiring
+        + WeightQuantize
+        + Into<<W as Semiring>::ReverseWeight>
+        + From<<W as Semiring>::ReverseWeight>,
+    <W as Semiring>::ReverseWeight: WeightQuantize + WeaklyDivisibleSemiring,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+{
+    let mut rewrites = top_rewrites(input, rule, 1, token_type)?;
+    Ok(rewrites.remove(0))
+}
+
+/// Returns the `nshortest` distinct rewrites of `input` by `rule` with the best weights,
+/// from the best to the worst.
+pub fn top_rewrites<W, F, I>(
+    input: &I,
+    rule: &Arc<F>,
+    nshortest: usize,
+    token_type: &TokenType,
+) -> Result<Vec<String>>
+where
+    W: Semiring
+        + WeightQuantize
+        + Into<<W as Semiring>::ReverseWeight>
+        + From<<W as Semiring>::ReverseWeight>,
+    <W as Semiring>::ReverseWeight: WeightQuantize + WeaklyDivisibleSemiring,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+{
+    let lattice = rewrite_lattice(input, rule, token_type)?;
+    let best: VectorFst<W> = shortest_path(&lattice, nshortest, true)?;
+    sorted_strings(best.paths_iter().collect(), token_type)
+}
+
+/// Returns all the distinct rewrites of `input` by `rule`, from the best weight to the worst.
+///
+/// Returns an error if there is an infinite number of rewrites.
+pub fn rewrites<W, F, I>(input: &I, rule: &Arc<F>, token_type: &TokenType) -> Result<Vec<String>>
+where
+    W: WeaklyDivisibleSemiring + WeightQuantize,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+{
+    let lattice = rewrite_lattice(input, rule, token_type)?;
+    if !lattice.properties()?.contains(FstProperties::ACYCLIC) {
+        bail!("Rewrite failure : the rule produces an infinite number of rewrites");
+    }
+    let lattice: VectorFst<W> =
+        determinize(Arc::new(lattice), DeterminizeType::DeterminizeFunctional)?;
+    sorted_strings(lattice.paths_iter().collect(), token_type)
+}
+
+/// Returns whether `rule` rewrites `input` into `output`.
+pub fn matches<W, F, I, O>(
+    input: &I,
+    output: &O,
+    rule: &Arc<F>,
+    token_type: &TokenType,
+) -> Result<bool>
+where
+    W: Semiring,
+    F: ExpandedFst<W>,
+    I: RewriteInput<W> + ?Sized,
+    O: RewriteInput<W> + ?Sized,
+{
+    let mut lattice = match accepted_lattice(input, rule, token_type)? {
+        Some(lattice) => lattice,
+        None => return Ok(false),
+    };
+    tr_sort(&mut lattice, olabel_compare);
+    let mut output_fst = output.to_acceptor(token_type)?;
+    tr_sort(&mut output_fst, ilabel_compare);
+    let fst: VectorFst<W> = compose(Arc::new(lattice), Arc::new(output_fst))?;
+    Ok(fst.start().is_some())
+}
+
+fn sorted_strings<W: Semiring>(
+    mut paths: Vec<FstPath<W>>,
+    token_type: &TokenType,
+) -> Result<Vec<String>> {
+    // The plus operation of the path semirings can't fail.
+    paths.sort_by(|a, b| {
+        if natural_less(&a.weight, &b.weight).unwrap_or(false) {
+            Ordering::Less
+        } else if natural_less(&b.weight, &a.weight).unwrap_or(false) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+    paths.iter().map(|p| print_string(p, token_type)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::closure::{closure, ClosureType};
+    use crate::fst_traits::MutableFst;
+    use crate::semirings::TropicalWeight;
+    use crate::utils::{string_map, transducer};
+    use crate::{SymbolTable, Tr};
+
+    fn rule() -> Result<Arc<VectorFst<TropicalWeight>>> {
+        let fst = string_map(
+            vec![
+                ("cat", "chat", TropicalWeight::new(1.0)),
+                ("cat", "matou", TropicalWeight::new(2.0)),
+                ("cat", "minou", TropicalWeight::new(3.0)),
+                ("dog", "chien", TropicalWeight::new(1.0)),
+            ],
+            &TokenType::Byte,
+            &TokenType::Byte,
+            true,
+        )?;
+        Ok(Arc::new(fst))
+    }
+
+    #[test]
+    fn test_top_rewrites() -> Result<()> {
+        let rule = rule()?;
+        assert_eq!(top_rewrite("dog", &rule, &TokenType::Byte)?, "chien");
+        assert_eq!(
+            top_rewrites("cat", &rule, 2, &TokenType::Byte)?,
+            vec!["chat", "matou"]
+        );
+        assert_eq!(
+            top_rewrites(&"cat".to_string(), &rule, 10, &TokenType::Byte)?,
+            vec!["chat", "matou", "minou"]
+        );
+        assert!(top_rewrite("bird", &rule, &TokenType::Byte).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrites() -> Result<()> {
+        let rule = rule()?;
+        assert_eq!(
+            rewrites("cat", &rule, &TokenType::Byte)?,
+            vec!["chat", "matou", "minou"]
+        );
+
+        let mut cyclic: VectorFst<TropicalWeight> = transducer(&[97], &[98], TropicalWeight::one());
+        let s = cyclic.add_state();
+        cyclic.add_tr(1, Tr::new(0, 99, TropicalWeight::one(), s))?;
+        cyclic.add_tr(s, Tr::new(0, 99, TropicalWeight::one(), 1))?;
+        assert_eq!(
+            top_rewrite("a", &Arc::new(cyclic.clone()), &TokenType::Byte)?,
+            "b"
+        );
+        assert!(rewrites("a", &Arc::new(cyclic), &TokenType::Byte).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches() -> Result<()> {
+        let rule = rule()?;
+        assert!(matches("cat", "matou", &rule, &TokenType::Byte)?);
+        assert!(!matches("cat", "chien", &rule, &TokenType::Byte)?);
+        assert!(!matches("bird", "chat", &rule, &TokenType::Byte)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_chained_paths() -> Result<()> {
+        let mut symt = SymbolTable::new();
+        symt.add_symbols(vec!["a", "b"]);
+        let token_type = TokenType::SymbolTable(Arc::new(symt));
+
+        // a -> b and b -> a everywhere.
+        let mut rule_ab: VectorFst<TropicalWeight> =
+            transducer(&[1], &[2], TropicalWeight::new(1.0));
+        closure(&mut rule_ab, ClosureType::ClosureStar);
+        let rule_ab = Arc::new(rule_ab);
+        let mut rule_ba: VectorFst<TropicalWeight> = transducer(&[2], &[1], TropicalWeight::one());
+        closure(&mut rule_ba, ClosureType::ClosureStar);
+        let rule_ba = Arc::new(rule_ba);
+
+        let lattice = rewrite_lattice("a a", &rule_ab, &token_type)?;
+        let path = lattice.paths_iter().next().unwrap();
+        assert_eq!(path.olabels, vec![2, 2]);
+        assert_eq!(path.weight, TropicalWeight::new(2.0));
+        assert_eq!(top_rewrite(&path, &rule_ba, &token_type)?, "a a");
+        assert!(matches(&path, "a a", &rule_ba, &token_type)?);
+        // Unknown symbols are errors, not mismatches.
+        assert!(matches("a c", "a a", &rule_ba, &token_type).is_err());
+        assert!(top_rewrite(&path, &rule_ab, &token_type).is_err());
+        Ok(())
+    }
+}
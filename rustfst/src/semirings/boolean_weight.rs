@@ -4,6 +4,8 @@ use crate::semirings::{CompleteSemiring, ReverseBack, Semiring, SemiringProperti
 use std::borrow::Borrow;
 /// Boolean semiring: (&, |, false, true).
 #[derive(Clone, Debug, PartialEq, PartialOrd, Default, Eq, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct BooleanWeight {
     value: bool,
 }
@@ -0,0 +1,261 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::Write;
+
+use anyhow::Result;
+use nom::IResult;
+
+#[cfg(test)]
+use crate::semirings::LogWeight;
+use crate::semirings::{
+    ProductWeight, ReverseBack, Semiring, SemiringProperties, SerializableSemiring, WeightQuantize,
+};
+
+/// Multiplication of a weight by a scalar belonging to another semiring W.
+/// Used by the `ExpectationWeight` to compute `a1·b2 + a2·b1` when the two components
+/// live in different semirings. Every semiring is trivially a module over itself.
+pub trait ScalarTimes<W: Semiring>: Semiring {
+    fn scalar_times(&self, scalar: &W) -> Result<Self>;
+}
+
+impl<W: Semiring> ScalarTimes<W> for W {
+    fn scalar_times(&self, scalar: &W) -> Result<Self> {
+        scalar.times(self)
+    }
+}
+
+/// Expectation semiring: W1 * W2.
+/// Plus is computed componentwise and times is defined as `(a1·b1, a1·b2 + a2·b1)`.
+/// When the first component holds the probability of a path and the second one the probability
+/// multiplied by a feature value, the shortest distance computes the expected value of the feature.
+/// The two components must be commutative semirings and W2 must be a module over W1.
+#[derive(Debug, Eq, PartialOrd, PartialEq, Clone, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ExpectationWeight<W1, W2>(ProductWeight<W1, W2>)
+where
+    W1: Semiring,
+    W2: Semiring;
+
+impl<W1, W2> AsRef<Self> for ExpectationWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    fn as_ref(&self) -> &ExpectationWeight<W1, W2> {
+        self
+    }
+}
+
+impl<W1, W2> Semiring for ExpectationWeight<W1, W2>
+where
+    W1: Semiring<ReverseWeight = W1>,
+    W2: Semiring<ReverseWeight = W2> + ScalarTimes<W1>,
+{
+    type Type = (W1, W2);
+    type ReverseWeight = ExpectationWeight<W1, W2>;
+
+    fn zero() -> Self {
+        Self::new((W1::zero(), W2::zero()))
+    }
+
+    fn one() -> Self {
+        Self::new((W1::one(), W2::zero()))
+    }
+
+    fn new(weight: <Self as Semiring>::Type) -> Self {
+        Self(ProductWeight::new(weight))
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        self.0.plus_assign(&rhs.borrow().0)
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        let mut value2 = self.value2().scalar_times(rhs.value1())?;
+        value2.plus_assign(rhs.value2().scalar_times(self.value1())?)?;
+        let value1 = self.value1().times(rhs.value1())?;
+        self.set_value((value1, value2));
+        Ok(())
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        self.0.value()
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.0.take_value()
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.0.set_value(value)
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        Ok(self.clone())
+    }
+
+    fn properties() -> SemiringProperties {
+        W1::properties()
+            & W2::properties()
+            & (SemiringProperties::LEFT_SEMIRING
+                | SemiringProperties::RIGHT_SEMIRING
+                | SemiringProperties::COMMUTATIVE
+                | SemiringProperties::IDEMPOTENT)
+    }
+}
+
+impl<W1, W2> ReverseBack<ExpectationWeight<W1, W2>> for ExpectationWeight<W1, W2>
+where
+    W1: Semiring<ReverseWeight = W1>,
+    W2: Semiring<ReverseWeight = W2> + ScalarTimes<W1>,
+{
+    fn reverse_back(&self) -> Result<ExpectationWeight<W1, W2>> {
+        Ok(self.clone())
+    }
+}
+
+impl<W1, W2> ExpectationWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    pub fn value1(&self) -> &W1 {
+        self.0.value1()
+    }
+
+    pub fn value2(&self) -> &W2 {
+        self.0.value2()
+    }
+
+    pub fn set_value1(&mut self, new_weight: W1) {
+        self.0.set_value1(new_weight);
+    }
+
+    pub fn set_value2(&mut self, new_weight: W2) {
+        self.0.set_value2(new_weight);
+    }
+}
+
+impl<W1, W2> From<(W1, W2)> for ExpectationWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    fn from(t: (W1, W2)) -> Self {
+        Self(ProductWeight::new(t))
+    }
+}
+
+impl<W1, W2> WeightQuantize for ExpectationWeight<W1, W2>
+where
+    W1: WeightQuantize<ReverseWeight = W1>,
+    W2: WeightQuantize<ReverseWeight = W2> + ScalarTimes<W1>,
+{
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        self.0.quantize_assign(delta)
+    }
+}
+
+impl<W1, W2> fmt::Display for ExpectationWeight<W1, W2>
+where
+    W1: SerializableSemiring,
+    W2: SerializableSemiring,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<W1, W2> SerializableSemiring for ExpectationWeight<W1, W2>
+where
+    W1: SerializableSemiring<ReverseWeight = W1>,
+    W2: SerializableSemiring<ReverseWeight = W2> + ScalarTimes<W1>,
+{
+    fn weight_type() -> String {
+        format!("expectation_{}_{}", W1::weight_type(), W2::weight_type())
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, w) = ProductWeight::<W1, W2>::parse_binary(i)?;
+        Ok((i, Self(w)))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.0.write_binary(file)
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, w) = ProductWeight::<W1, W2>::parse_text(i)?;
+        Ok((i, Self(w)))
+    }
+}
+
+test_semiring_serializable!(
+    tests_expectation_weight_serializable,
+    ExpectationWeight::<LogWeight, LogWeight>,
+    ExpectationWeight::new((LogWeight::new(0.2), LogWeight::new(1.7)))
+    ExpectationWeight::zero()
+    ExpectationWeight::one()
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::shortest_distance;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::MutableFst;
+    use crate::semirings::ProbabilityWeight;
+    use crate::Tr;
+
+    type ExpWeight = ExpectationWeight<ProbabilityWeight, ProbabilityWeight>;
+
+    fn w(p: f32, r: f32) -> ExpWeight {
+        ExpectationWeight::new((ProbabilityWeight::new(p), ProbabilityWeight::new(p * r)))
+    }
+
+    #[test]
+    fn test_expectation_weight_plus_times() -> Result<()> {
+        let a = ExpWeight::new((ProbabilityWeight::new(2.0), ProbabilityWeight::new(3.0)));
+        let b = ExpWeight::new((ProbabilityWeight::new(5.0), ProbabilityWeight::new(7.0)));
+        assert_eq!(
+            a.times(&b)?,
+            ExpWeight::new((ProbabilityWeight::new(10.0), ProbabilityWeight::new(29.0)))
+        );
+        assert_eq!(
+            a.plus(&b)?,
+            ExpWeight::new((ProbabilityWeight::new(7.0), ProbabilityWeight::new(10.0)))
+        );
+        assert_eq!(a.times(ExpWeight::one())?, a);
+        assert_eq!(a.times(ExpWeight::zero())?, ExpWeight::zero());
+        assert_eq!(a.plus(ExpWeight::zero())?, a);
+        assert_eq!(
+            ExpectationWeight::<LogWeight, LogWeight>::weight_type(),
+            "expectation_log_log"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_expectation_weight_shortest_distance() -> Result<()> {
+        // Two paths with probabilities 0.3 and 0.7 and a feature counting the number of arcs
+        // labeled 1 : 2 on the first path and 1 on the second.
+        let mut fst = VectorFst::<ExpWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, w(0.3, 1.0), s1))?;
+        fst.add_tr(s0, Tr::new(2, 2, w(0.7, 0.0), s1))?;
+        fst.add_tr(s1, Tr::new(1, 1, w(1.0, 1.0), s2))?;
+        fst.set_final(s2, ExpWeight::one())?;
+
+        let distances = shortest_distance(&fst, false)?;
+        let total = &distances[s2];
+        assert!((total.value1().value() - 1.0).abs() < 1e-6);
+        assert!((total.value2().value() - (0.3 * 2.0 + 0.7 * 1.0)).abs() < 1e-6);
+        Ok(())
+    }
+}
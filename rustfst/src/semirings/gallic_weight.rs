@@ -18,24 +18,28 @@ use crate::Label;
 
 /// Product of StringWeightLeft and an arbitrary weight.
 #[derive(PartialOrd, PartialEq, Eq, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GallicWeightLeft<W>(ProductWeight<StringWeightLeft, W>)
 where
     W: Semiring;
 
 /// Product of StringWeightRight and an arbitrary weight.
 #[derive(PartialOrd, PartialEq, Eq, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GallicWeightRight<W>(ProductWeight<StringWeightRight, W>)
 where
     W: Semiring;
 
 /// Product of StringWeighRestrict and an arbitrary weight.
 #[derive(PartialOrd, PartialEq, Eq, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GallicWeightRestrict<W>(ProductWeight<StringWeightRestrict, W>)
 where
     W: Semiring;
 
 /// Product of StringWeightRestrict and an arbitrary weight.
 #[derive(PartialOrd, PartialEq, Eq, Clone, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GallicWeightMin<W>(ProductWeight<StringWeightRestrict, W>)
 where
     W: Semiring;
@@ -316,6 +320,7 @@ impl<W: Semiring> UnionWeightOption<GallicWeightRestrict<W>>
 
 /// UnionWeight of GallicWeightRestrict.
 #[derive(Debug, PartialOrd, PartialEq, Clone, Hash, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GallicWeight<W>(
     pub UnionWeight<GallicWeightRestrict<W>, GallicUnionWeightOption<GallicWeightRestrict<W>>>,
 )
@@ -7,6 +7,8 @@ use crate::semirings::{CompleteSemiring, ReverseBack, Semiring, SemiringProperti
 
 /// Probability semiring: (x, +, 0.0, 1.0).
 #[derive(Clone, Debug, PartialEq, PartialOrd, Default, Hash, Eq, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct IntegerWeight {
     value: i32,
 }
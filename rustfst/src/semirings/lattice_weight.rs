@@ -0,0 +1,504 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::f32;
+use std::fmt;
+use std::io::Write;
+
+use anyhow::Result;
+use nom::bytes::complete::tag;
+use nom::multi::{count, separated_list};
+use nom::number::complete::le_i32;
+use nom::IResult;
+
+use crate::parsers::bin_fst::utils_serialization::write_bin_i32;
+use crate::parsers::nom_utils::num;
+use crate::semirings::{
+    DivideType, ProductWeight, ReverseBack, Semiring, SemiringProperties, SerializableSemiring,
+    TropicalWeight, WeaklyDivisibleSemiring, WeightQuantize,
+};
+use crate::Label;
+
+/// Kaldi lattice semiring: a pair of costs (graph cost, acoustic cost).
+/// Plus returns the weight with the lowest total cost, ties being broken with the graph cost.
+/// Times adds the costs componentwise.
+/// Binary FSTs with this weight use the `lattice4` tr type, as written by Kaldi.
+#[derive(Debug, Eq, PartialOrd, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LatticeWeight(ProductWeight<TropicalWeight, TropicalWeight>);
+
+impl LatticeWeight {
+    pub fn value1(&self) -> &TropicalWeight {
+        self.0.value1()
+    }
+
+    pub fn value2(&self) -> &TropicalWeight {
+        self.0.value2()
+    }
+
+    pub fn set_value1(&mut self, new_weight: TropicalWeight) {
+        self.0.set_value1(new_weight);
+    }
+
+    pub fn set_value2(&mut self, new_weight: TropicalWeight) {
+        self.0.set_value2(new_weight);
+    }
+
+    /// Cost coming from the decoding graph (LM, lexicon, transition probabilities).
+    pub fn graph_cost(&self) -> f32 {
+        *self.value1().value()
+    }
+
+    /// Cost coming from the acoustic model.
+    pub fn acoustic_cost(&self) -> f32 {
+        *self.value2().value()
+    }
+
+    /// Kaldi ordering : the weight with the lowest total cost is the greatest.
+    fn compare(&self, other: &Self) -> Ordering {
+        let f1 = self.graph_cost() + self.acoustic_cost();
+        let f2 = other.graph_cost() + other.acoustic_cost();
+        if f1 < f2 {
+            Ordering::Greater
+        } else if f1 > f2 {
+            Ordering::Less
+        } else if self.graph_cost() < other.graph_cost() {
+            Ordering::Greater
+        } else if self.graph_cost() > other.graph_cost() {
+            Ordering::Less
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+impl AsRef<LatticeWeight> for LatticeWeight {
+    fn as_ref(&self) -> &LatticeWeight {
+        self
+    }
+}
+
+impl Semiring for LatticeWeight {
+    type Type = (TropicalWeight, TropicalWeight);
+    type ReverseWeight = LatticeWeight;
+
+    fn zero() -> Self {
+        Self::from((f32::INFINITY, f32::INFINITY))
+    }
+
+    fn one() -> Self {
+        Self::from((0.0, 0.0))
+    }
+
+    fn new(weight: <Self as Semiring>::Type) -> Self {
+        Self(ProductWeight::new(weight))
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        if self.compare(rhs) == Ordering::Less {
+            *self = rhs.clone();
+        }
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        self.0.times_assign(&rhs.borrow().0)
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        self.0.value()
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.0.take_value()
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.0.set_value(value)
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        Ok(self.clone())
+    }
+
+    fn properties() -> SemiringProperties {
+        SemiringProperties::LEFT_SEMIRING
+            | SemiringProperties::RIGHT_SEMIRING
+            | SemiringProperties::COMMUTATIVE
+            | SemiringProperties::PATH
+            | SemiringProperties::IDEMPOTENT
+    }
+}
+
+impl ReverseBack<LatticeWeight> for LatticeWeight {
+    fn reverse_back(&self) -> Result<LatticeWeight> {
+        Ok(self.clone())
+    }
+}
+
+impl Default for LatticeWeight {
+    fn default() -> Self {
+        Self::one()
+    }
+}
+
+impl From<(f32, f32)> for LatticeWeight {
+    fn from(costs: (f32, f32)) -> Self {
+        Self::new((TropicalWeight::new(costs.0), TropicalWeight::new(costs.1)))
+    }
+}
+
+impl WeaklyDivisibleSemiring for LatticeWeight {
+    fn divide_assign(&mut self, rhs: &Self, _divide_type: DivideType) -> Result<()> {
+        let a = self.graph_cost() - rhs.graph_cost();
+        let b = self.acoustic_cost() - rhs.acoustic_cost();
+        // Same as Kaldi : invalid results (e.g dividing by zero) are mapped to zero.
+        if a.is_nan() || b.is_nan() || a == f32::NEG_INFINITY || b == f32::NEG_INFINITY {
+            *self = Self::zero();
+        } else {
+            *self = Self::from((a, b));
+        }
+        Ok(())
+    }
+}
+
+impl WeightQuantize for LatticeWeight {
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        self.0.quantize_assign(delta)
+    }
+}
+
+impl fmt::Display for LatticeWeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl SerializableSemiring for LatticeWeight {
+    fn weight_type() -> String {
+        "lattice4".to_string()
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, w) = ProductWeight::<TropicalWeight, TropicalWeight>::parse_binary(i)?;
+        Ok((i, Self(w)))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.0.write_binary(file)
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, w) = ProductWeight::<TropicalWeight, TropicalWeight>::parse_text(i)?;
+        Ok((i, Self(w)))
+    }
+}
+
+/// Kaldi compact lattice semiring: a `LatticeWeight` and a string of transition ids.
+/// Plus returns the weight with the best `LatticeWeight`, ties being broken using the strings.
+/// Times multiplies the lattice weights and concatenates the strings.
+/// Binary FSTs with this weight use the `compactlattice44` tr type, as written by Kaldi.
+#[derive(Debug, Eq, PartialOrd, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct CompactLatticeWeight {
+    value: (LatticeWeight, Vec<Label>),
+}
+
+impl CompactLatticeWeight {
+    pub fn weight(&self) -> &LatticeWeight {
+        &self.value.0
+    }
+
+    pub fn string(&self) -> &[Label] {
+        &self.value.1
+    }
+
+    pub fn set_weight(&mut self, weight: LatticeWeight) {
+        self.value.0 = weight;
+    }
+
+    pub fn set_string(&mut self, string: Vec<Label>) {
+        self.value.1 = string;
+    }
+
+    /// Kaldi ordering : compares the lattice weights then the strings. The shortest string wins.
+    fn compare(&self, other: &Self) -> Ordering {
+        let c = self.weight().compare(other.weight());
+        if c != Ordering::Equal {
+            return c;
+        }
+        let (s1, s2) = (self.string(), other.string());
+        if s1.len() != s2.len() {
+            return s2.len().cmp(&s1.len());
+        }
+        s1.cmp(s2)
+    }
+}
+
+impl AsRef<CompactLatticeWeight> for CompactLatticeWeight {
+    fn as_ref(&self) -> &CompactLatticeWeight {
+        self
+    }
+}
+
+impl Semiring for CompactLatticeWeight {
+    type Type = (LatticeWeight, Vec<Label>);
+    type ReverseWeight = CompactLatticeWeight;
+
+    fn zero() -> Self {
+        Self::new((LatticeWeight::zero(), vec![]))
+    }
+
+    fn one() -> Self {
+        Self::new((LatticeWeight::one(), vec![]))
+    }
+
+    fn new(value: <Self as Semiring>::Type) -> Self {
+        Self { value }
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        if self.compare(rhs) == Ordering::Less {
+            *self = rhs.clone();
+        }
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        self.value.0.times_assign(rhs.weight())?;
+        if self.value.0.is_zero() {
+            self.value.1.clear();
+        } else {
+            self.value.1.extend_from_slice(rhs.string());
+        }
+        Ok(())
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        &self.value
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.value
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.value = value
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        let string = self.string().iter().rev().cloned().collect();
+        Ok(Self::new((self.weight().reverse()?, string)))
+    }
+
+    fn properties() -> SemiringProperties {
+        LatticeWeight::properties()
+            & (SemiringProperties::LEFT_SEMIRING
+                | SemiringProperties::RIGHT_SEMIRING
+                | SemiringProperties::PATH
+                | SemiringProperties::IDEMPOTENT)
+    }
+}
+
+impl ReverseBack<CompactLatticeWeight> for CompactLatticeWeight {
+    fn reverse_back(&self) -> Result<CompactLatticeWeight> {
+        self.reverse()
+    }
+}
+
+impl Default for CompactLatticeWeight {
+    fn default() -> Self {
+        Self::one()
+    }
+}
+
+impl WeaklyDivisibleSemiring for CompactLatticeWeight {
+    fn divide_assign(&mut self, rhs: &Self, divide_type: DivideType) -> Result<()> {
+        let weight = self.weight().divide(rhs.weight(), divide_type)?;
+        if weight.is_zero() {
+            *self = Self::zero();
+            return Ok(());
+        }
+        let (s1, s2) = (self.string(), rhs.string());
+        if s2.len() > s1.len() {
+            bail!(
+                "Cannot divide, length mismatch : {:?} {:?}",
+                self.string(),
+                rhs.string()
+            );
+        }
+        let string = match divide_type {
+            DivideType::DivideLeft => {
+                if !s1.starts_with(s2) {
+                    bail!("Cannot divide, data mismatch : {:?} {:?}", s1, s2);
+                }
+                s1[s2.len()..].to_vec()
+            }
+            DivideType::DivideRight => {
+                if !s1.ends_with(s2) {
+                    bail!("Cannot divide, data mismatch : {:?} {:?}", s1, s2);
+                }
+                s1[..s1.len() - s2.len()].to_vec()
+            }
+            DivideType::DivideAny => {
+                bail!("Cannot divide CompactLatticeWeight with DivideAny")
+            }
+        };
+        *self = Self::new((weight, string));
+        Ok(())
+    }
+}
+
+impl WeightQuantize for CompactLatticeWeight {
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        self.value.0.quantize_assign(delta)
+    }
+}
+
+impl fmt::Display for CompactLatticeWeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},", self.weight())?;
+        for (idx, label) in self.string().iter().enumerate() {
+            if idx > 0 {
+                write!(f, "_")?;
+            }
+            write!(f, "{}", label)?;
+        }
+        Ok(())
+    }
+}
+
+impl SerializableSemiring for CompactLatticeWeight {
+    fn weight_type() -> String {
+        format!("compact{}4", LatticeWeight::weight_type())
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, weight) = LatticeWeight::parse_binary(i)?;
+        let (i, n) = le_i32(i)?;
+        let (i, string) = count(le_i32, n as usize)(i)?;
+        Ok((
+            i,
+            Self::new((weight, string.into_iter().map(|l| l as Label).collect())),
+        ))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.weight().write_binary(file)?;
+        write_bin_i32(file, self.string().len() as i32)?;
+        for label in self.string() {
+            write_bin_i32(file, *label as i32)?;
+        }
+        Ok(())
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, weight) = LatticeWeight::parse_text(i)?;
+        let (i, _) = tag(",")(i)?;
+        let (i, string) = separated_list(tag("_"), num)(i)?;
+        Ok((i, Self::new((weight, string))))
+    }
+}
+
+test_semiring_serializable!(
+    tests_lattice_weight_serializable,
+    LatticeWeight,
+    LatticeWeight::one() LatticeWeight::zero() LatticeWeight::from((0.5, 2.25)) LatticeWeight::from((-1.0, 3.0))
+);
+
+test_semiring_serializable!(
+    tests_compact_lattice_weight_serializable,
+    CompactLatticeWeight,
+    CompactLatticeWeight::one()
+    CompactLatticeWeight::zero()
+    CompactLatticeWeight::new((LatticeWeight::from((0.5, 2.25)), vec![3]))
+    CompactLatticeWeight::new((LatticeWeight::from((-1.0, 3.0)), vec![4, 4, 12]))
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::algorithms::determinize::{determinize, DeterminizeType};
+    use crate::algorithms::shortest_path;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{MutableFst, PathsIterator, SerializableFst};
+    use crate::Tr;
+
+    fn clw(graph: f32, acoustic: f32, string: Vec<Label>) -> CompactLatticeWeight {
+        CompactLatticeWeight::new((LatticeWeight::from((graph, acoustic)), string))
+    }
+
+    #[test]
+    fn test_lattice_weight_plus_times() -> Result<()> {
+        let a = LatticeWeight::from((1.0, 2.0));
+        let b = LatticeWeight::from((2.5, 0.0));
+        assert_eq!(a.plus(&b)?, b);
+        assert_eq!(b.plus(&a)?, b);
+        assert_eq!(a.plus(LatticeWeight::from((2.0, 1.0)))?, a);
+        assert_eq!(a.times(&b)?, LatticeWeight::from((3.5, 2.0)));
+        assert_eq!(a.times(LatticeWeight::zero())?, LatticeWeight::zero());
+        assert_eq!(a.plus(LatticeWeight::zero())?, a);
+        assert_eq!(
+            a.times(&b)?.divide(&b, DivideType::DivideAny)?,
+            LatticeWeight::from((1.0, 2.0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_lattice_weight_plus_times() -> Result<()> {
+        let a = clw(1.0, 2.0, vec![1, 2]);
+        let b = clw(1.0, 2.0, vec![3]);
+        assert_eq!(a.plus(&b)?, b);
+        assert_eq!(a.times(&b)?, clw(2.0, 4.0, vec![1, 2, 3]));
+        assert_eq!(
+            a.times(CompactLatticeWeight::zero())?,
+            CompactLatticeWeight::zero()
+        );
+        assert_eq!(a.times(CompactLatticeWeight::one())?, a);
+        assert_eq!(a.times(&b)?.divide(&a, DivideType::DivideLeft)?, b);
+        assert_eq!(a.times(&b)?.divide(&b, DivideType::DivideRight)?, a);
+        assert!(a.divide(&b, DivideType::DivideLeft).is_err());
+        assert_eq!(format!("{}", a), "1,2,1_2");
+        Ok(())
+    }
+
+    #[test]
+    fn test_kaldi_lattice_serialization() -> Result<()> {
+        let mut fst = VectorFst::<CompactLatticeWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, clw(1.0, 2.0, vec![5, 5]), s1))?;
+        fst.add_tr(s0, Tr::new(1, 1, clw(0.5, 1.0, vec![6]), s1))?;
+        fst.add_tr(s1, Tr::new(2, 2, clw(0.0, 3.0, vec![7]), s2))?;
+        fst.set_final(s2, clw(0.25, 0.0, vec![]))?;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("lattice.fst");
+        fst.write(&path)?;
+        let bytes = std::fs::read(&path)?;
+        let tr_type = b"compactlattice44";
+        assert!(bytes.windows(tr_type.len()).any(|w| w == tr_type));
+        let read_fst = VectorFst::<CompactLatticeWeight>::read(&path)?;
+        assert_eq!(read_fst, fst);
+
+        let det: VectorFst<CompactLatticeWeight> = determinize(
+            Arc::new(fst.clone()),
+            DeterminizeType::DeterminizeFunctional,
+        )?;
+        let best: VectorFst<CompactLatticeWeight> = shortest_path(&det, 1, false)?;
+        let paths: Vec<_> = best.paths_iter().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].weight, clw(0.75, 4.0, vec![6, 7]));
+        Ok(())
+    }
+}
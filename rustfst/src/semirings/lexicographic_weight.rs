@@ -0,0 +1,268 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::Write;
+
+use anyhow::Result;
+use nom::IResult;
+
+use crate::algorithms::queues::natural_less;
+#[cfg(test)]
+use crate::semirings::TropicalWeight;
+use crate::semirings::{
+    DivideType, ProductWeight, ReverseBack, Semiring, SemiringProperties, SerializableSemiring,
+    WeaklyDivisibleSemiring, WeightQuantize,
+};
+
+/// Lexicographic semiring: W1 * W2.
+/// Plus returns the minimum of the two weights for the lexicographic order built on
+/// the natural orders of W1 and W2. Times is computed componentwise.
+/// W1 and W2 must both be path semirings.
+#[derive(Debug, Eq, PartialOrd, PartialEq, Clone, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct LexicographicWeight<W1, W2>(ProductWeight<W1, W2>)
+where
+    W1: Semiring,
+    W2: Semiring;
+
+impl<W1, W2> AsRef<Self> for LexicographicWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    fn as_ref(&self) -> &LexicographicWeight<W1, W2> {
+        self
+    }
+}
+
+impl<W1, W2> Semiring for LexicographicWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    type Type = (W1, W2);
+    type ReverseWeight = LexicographicWeight<W1::ReverseWeight, W2::ReverseWeight>;
+
+    fn zero() -> Self {
+        Self(ProductWeight::zero())
+    }
+
+    fn one() -> Self {
+        Self(ProductWeight::one())
+    }
+
+    fn new(weight: <Self as Semiring>::Type) -> Self {
+        Self(ProductWeight::new(weight))
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        if natural_less(self.value1(), rhs.value1())? {
+            return Ok(());
+        }
+        if natural_less(rhs.value1(), self.value1())? {
+            self.set_value(rhs.value().clone());
+            return Ok(());
+        }
+        if natural_less(rhs.value2(), self.value2())? {
+            self.set_value(rhs.value().clone());
+        }
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        self.0.times_assign(&rhs.borrow().0)
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        self.0.value()
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.0.take_value()
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.0.set_value(value)
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        Ok(LexicographicWeight(self.0.reverse()?))
+    }
+
+    fn properties() -> SemiringProperties {
+        W1::properties()
+            & W2::properties()
+            & (SemiringProperties::LEFT_SEMIRING
+                | SemiringProperties::RIGHT_SEMIRING
+                | SemiringProperties::PATH
+                | SemiringProperties::IDEMPOTENT
+                | SemiringProperties::COMMUTATIVE)
+    }
+}
+
+impl<W1: Semiring, W2: Semiring> ReverseBack<LexicographicWeight<W1, W2>>
+    for <LexicographicWeight<W1, W2> as Semiring>::ReverseWeight
+{
+    fn reverse_back(&self) -> Result<LexicographicWeight<W1, W2>> {
+        Ok(LexicographicWeight(self.0.reverse_back()?))
+    }
+}
+
+impl<W1, W2> LexicographicWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    pub fn value1(&self) -> &W1 {
+        self.0.value1()
+    }
+
+    pub fn value2(&self) -> &W2 {
+        self.0.value2()
+    }
+
+    pub fn set_value1(&mut self, new_weight: W1) {
+        self.0.set_value1(new_weight);
+    }
+
+    pub fn set_value2(&mut self, new_weight: W2) {
+        self.0.set_value2(new_weight);
+    }
+}
+
+impl<W1, W2> From<(W1, W2)> for LexicographicWeight<W1, W2>
+where
+    W1: Semiring,
+    W2: Semiring,
+{
+    fn from(t: (W1, W2)) -> Self {
+        Self::new(t)
+    }
+}
+
+impl<W1, W2> WeaklyDivisibleSemiring for LexicographicWeight<W1, W2>
+where
+    W1: WeaklyDivisibleSemiring,
+    W2: WeaklyDivisibleSemiring,
+{
+    fn divide_assign(&mut self, rhs: &Self, divide_type: DivideType) -> Result<()> {
+        self.0.divide_assign(&rhs.0, divide_type)
+    }
+}
+
+impl<W1, W2> WeightQuantize for LexicographicWeight<W1, W2>
+where
+    W1: WeightQuantize,
+    W2: WeightQuantize,
+{
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        self.0.quantize_assign(delta)
+    }
+}
+
+impl<W1, W2> fmt::Display for LexicographicWeight<W1, W2>
+where
+    W1: SerializableSemiring,
+    W2: SerializableSemiring,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<W1, W2> SerializableSemiring for LexicographicWeight<W1, W2>
+where
+    W1: SerializableSemiring,
+    W2: SerializableSemiring,
+{
+    fn weight_type() -> String {
+        format!("{}_LT_{}", W1::weight_type(), W2::weight_type())
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, w) = ProductWeight::<W1, W2>::parse_binary(i)?;
+        Ok((i, Self(w)))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.0.write_binary(file)
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, w) = ProductWeight::<W1, W2>::parse_text(i)?;
+        Ok((i, Self(w)))
+    }
+}
+
+test_semiring_serializable!(
+    tests_lexicographic_weight_serializable,
+    LexicographicWeight::<TropicalWeight, TropicalWeight>,
+    LexicographicWeight::new((TropicalWeight::new(0.2), TropicalWeight::new(1.7)))
+    LexicographicWeight::zero()
+    LexicographicWeight::one()
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::determinize::{determinize, DeterminizeType};
+    use crate::algorithms::{minimize, shortest_path};
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{ExpandedFst, MutableFst, PathsIterator};
+    use crate::Tr;
+    use std::sync::Arc;
+
+    type LexWeight = LexicographicWeight<TropicalWeight, TropicalWeight>;
+
+    fn w(v1: f32, v2: f32) -> LexWeight {
+        LexicographicWeight::new((TropicalWeight::new(v1), TropicalWeight::new(v2)))
+    }
+
+    #[test]
+    fn test_lexicographic_weight_plus_times() -> Result<()> {
+        assert_eq!(w(1.0, 5.0).plus(w(2.0, 0.0))?, w(1.0, 5.0));
+        assert_eq!(w(2.0, 0.0).plus(w(1.0, 5.0))?, w(1.0, 5.0));
+        assert_eq!(w(1.0, 5.0).plus(w(1.0, 3.0))?, w(1.0, 3.0));
+        assert_eq!(w(1.0, 5.0).plus(LexWeight::zero())?, w(1.0, 5.0));
+        assert_eq!(w(1.0, 5.0).times(w(2.0, 3.0))?, w(3.0, 8.0));
+        assert_eq!(w(1.0, 5.0).times(LexWeight::one())?, w(1.0, 5.0));
+        assert_eq!(
+            w(3.0, 8.0).divide(&w(2.0, 3.0), DivideType::DivideAny)?,
+            w(1.0, 5.0)
+        );
+        assert!(LexWeight::properties().contains(SemiringProperties::PATH));
+        assert_eq!(LexWeight::weight_type(), "tropical_LT_tropical");
+        Ok(())
+    }
+
+    #[test]
+    fn test_lexicographic_weight_algorithms() -> Result<()> {
+        // Two paths with the same first component : the second one breaks the tie.
+        let mut fst = VectorFst::<LexWeight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, w(1.0, 4.0), s1))?;
+        fst.add_tr(s0, Tr::new(2, 2, w(1.0, 2.0), s1))?;
+        fst.add_tr(s0, Tr::new(3, 3, w(2.0, 0.0), s1))?;
+        fst.add_tr(s1, Tr::new(4, 4, w(1.0, 1.0), s2))?;
+        fst.set_final(s2, LexWeight::one())?;
+
+        let best: VectorFst<LexWeight> = shortest_path(&fst, 1, false)?;
+        let paths: Vec<_> = best.paths_iter().collect();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].ilabels, vec![2, 4]);
+        assert_eq!(paths[0].weight, w(2.0, 3.0));
+
+        let mut det: VectorFst<LexWeight> = determinize(
+            Arc::new(fst.clone()),
+            DeterminizeType::DeterminizeFunctional,
+        )?;
+        minimize(&mut det, false)?;
+        assert_eq!(det.num_states(), 3);
+        Ok(())
+    }
+}
@@ -131,6 +131,8 @@ impl WeaklyDivisibleSemiring for LogWeight {
 
 impl_quantize_f32!(LogWeight);
 
+impl_serde_f32!(LogWeight);
+
 partial_eq_and_hash_f32!(LogWeight);
 
 impl SerializableSemiring for LogWeight {
@@ -5,32 +5,50 @@ mod semiring;
 mod macros;
 
 mod boolean_weight;
+mod expectation_weight;
 mod gallic_weight;
 mod integer_weight;
+mod lattice_weight;
+mod lexicographic_weight;
 mod log_weight;
+mod log_weight_64;
 mod power_weight;
 mod probability_weight;
 mod product_weight;
+mod set_weight;
+mod signed_log_weight;
+mod sparse_power_weight;
 mod string_variant;
 mod string_weight;
 mod tropical_weight;
+mod tropical_weight_64;
 mod union_weight;
 
 pub use self::boolean_weight::BooleanWeight;
+pub use self::expectation_weight::{ExpectationWeight, ScalarTimes};
 pub use self::gallic_weight::{
     GallicWeight, GallicWeightLeft, GallicWeightMin, GallicWeightRestrict, GallicWeightRight,
 };
 pub use self::integer_weight::IntegerWeight;
+pub use self::lattice_weight::{CompactLatticeWeight, LatticeWeight};
+pub use self::lexicographic_weight::LexicographicWeight;
 pub use self::log_weight::LogWeight;
+pub use self::log_weight_64::LogWeight64;
 pub use self::probability_weight::ProbabilityWeight;
 pub use self::product_weight::ProductWeight;
 pub use self::semiring::{
     CompleteSemiring, DivideType, ReverseBack, Semiring, SemiringProperties, SerializableSemiring,
     StarSemiring, WeaklyDivisibleSemiring, WeightQuantize,
 };
+pub use self::set_weight::{
+    SetIntersectUnion, SetUnionIntersect, SetWeight, SetWeightOption, SetWeightVariant,
+};
+pub use self::signed_log_weight::SignedLogWeight;
+pub use self::sparse_power_weight::SparsePowerWeight;
 pub(crate) use self::string_variant::StringWeightVariant;
 pub use self::string_weight::{
     StringType, StringWeightLeft, StringWeightRestrict, StringWeightRight,
 };
 pub use self::tropical_weight::TropicalWeight;
+pub use self::tropical_weight_64::TropicalWeight64;
 pub use self::union_weight::{UnionWeight, UnionWeightOption};
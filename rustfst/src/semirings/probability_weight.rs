@@ -129,6 +129,8 @@ impl WeaklyDivisibleSemiring for ProbabilityWeight {
 
 impl_quantize_f32!(ProbabilityWeight);
 
+impl_serde_f32!(ProbabilityWeight);
+
 partial_eq_and_hash_f32!(ProbabilityWeight);
 
 test_semiring_serializable!(
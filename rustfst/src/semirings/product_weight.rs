@@ -15,6 +15,8 @@ use crate::semirings::{LogWeight, TropicalWeight};
 
 /// Product semiring: W1 * W2.
 #[derive(Debug, Eq, PartialOrd, PartialEq, Clone, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct ProductWeight<W1, W2>
 where
     W1: Semiring,
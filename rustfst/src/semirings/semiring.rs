@@ -140,6 +140,68 @@ macro_rules! impl_quantize_f32 {
     };
 }
 
+macro_rules! impl_quantize_f64 {
+    ($semiring: ident) => {
+        impl WeightQuantize for $semiring {
+            fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+                let v = *self.value();
+                if v.is_infinite() {
+                    return Ok(());
+                }
+                let delta = f64::from(delta);
+                self.set_value(((v / delta) + 0.5).floor() * delta);
+                Ok(())
+            }
+        }
+    };
+}
+
+macro_rules! impl_serde_f32 {
+    ($semiring: ident) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $semiring {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                crate::parsers::serde_utils::serialize_f32(*self.value(), serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $semiring {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                crate::parsers::serde_utils::deserialize_f32(deserializer).map(Self::new)
+            }
+        }
+    };
+}
+
+macro_rules! impl_serde_f64 {
+    ($semiring: ident) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $semiring {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                crate::parsers::serde_utils::serialize_f64(*self.value(), serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $semiring {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                crate::parsers::serde_utils::deserialize_f64(deserializer).map(Self::new)
+            }
+        }
+    };
+}
+
 macro_rules! display_semiring {
     ($semiring:tt) => {
         use std::fmt;
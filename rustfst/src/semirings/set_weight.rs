@@ -0,0 +1,435 @@
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::hash::Hash;
+use std::io::Write;
+use std::marker::PhantomData;
+
+use anyhow::Result;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::multi::{count, separated_list};
+use nom::number::complete::le_i32;
+use nom::IResult;
+
+use crate::parsers::bin_fst::utils_serialization::write_bin_i32;
+use crate::parsers::nom_utils::num;
+use crate::semirings::{
+    DivideType, ReverseBack, Semiring, SemiringProperties, SerializableSemiring,
+    WeaklyDivisibleSemiring, WeightQuantize,
+};
+use crate::Label;
+
+/// Label used by OpenFst to represent the universal set in the binary format.
+const SET_UNIV_LABEL: i32 = -1;
+
+/// Value of a `SetWeight` : either the universal set or a finite set of labels.
+#[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum SetWeightVariant {
+    Universal,
+    /// Sorted list of labels without duplicates.
+    Labels(Vec<Label>),
+}
+
+impl SetWeightVariant {
+    fn union(&self, other: &Self) -> Self {
+        match (self, other) {
+            (SetWeightVariant::Labels(l1), SetWeightVariant::Labels(l2)) => {
+                let mut labels = Vec::with_capacity(l1.len() + l2.len());
+                let (mut i1, mut i2) = (0, 0);
+                while i1 < l1.len() && i2 < l2.len() {
+                    if l1[i1] < l2[i2] {
+                        labels.push(l1[i1]);
+                        i1 += 1;
+                    } else if l1[i1] > l2[i2] {
+                        labels.push(l2[i2]);
+                        i2 += 1;
+                    } else {
+                        labels.push(l1[i1]);
+                        i1 += 1;
+                        i2 += 1;
+                    }
+                }
+                labels.extend_from_slice(&l1[i1..]);
+                labels.extend_from_slice(&l2[i2..]);
+                SetWeightVariant::Labels(labels)
+            }
+            _ => SetWeightVariant::Universal,
+        }
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        match (self, other) {
+            (SetWeightVariant::Universal, v) | (v, SetWeightVariant::Universal) => v.clone(),
+            (SetWeightVariant::Labels(l1), SetWeightVariant::Labels(l2)) => {
+                let labels = l1
+                    .iter()
+                    .filter(|l| l2.binary_search(l).is_ok())
+                    .cloned()
+                    .collect();
+                SetWeightVariant::Labels(labels)
+            }
+        }
+    }
+
+    fn difference(&self, other: &Self) -> Result<Self> {
+        match (self, other) {
+            (_, SetWeightVariant::Universal) => Ok(SetWeightVariant::Labels(vec![])),
+            (SetWeightVariant::Universal, SetWeightVariant::Labels(l)) => {
+                if l.is_empty() {
+                    Ok(SetWeightVariant::Universal)
+                } else {
+                    bail!("The complement of a finite set can't be represented")
+                }
+            }
+            (SetWeightVariant::Labels(l1), SetWeightVariant::Labels(l2)) => {
+                let labels = l1
+                    .iter()
+                    .filter(|l| l2.binary_search(l).is_err())
+                    .cloned()
+                    .collect();
+                Ok(SetWeightVariant::Labels(labels))
+            }
+        }
+    }
+}
+
+/// Determines which set operations are used as Plus and Times by a `SetWeight`.
+pub trait SetWeightOption: Debug + Hash + Clone + PartialOrd + Eq + Sync + 'static {
+    /// If true, Plus is the union and Times the intersection. Otherwise, Plus is the
+    /// intersection and Times the union.
+    const UNION_INTERSECT: bool;
+}
+
+/// Plus is the intersection, Times is the union. Zero is the universal set and One the empty set.
+#[derive(Debug, Hash, Clone, PartialOrd, PartialEq, Eq)]
+pub struct SetIntersectUnion {}
+
+impl SetWeightOption for SetIntersectUnion {
+    const UNION_INTERSECT: bool = false;
+}
+
+/// Plus is the union, Times is the intersection. Zero is the empty set and One the universal set.
+#[derive(Debug, Hash, Clone, PartialOrd, PartialEq, Eq)]
+pub struct SetUnionIntersect {}
+
+impl SetWeightOption for SetUnionIntersect {
+    const UNION_INTERSECT: bool = true;
+}
+
+/// Set semiring: the weights are sets of labels, combined with union and intersection.
+/// The option O selects which operation is Plus, the other one being Times.
+/// By default, Plus is the intersection and Times the union, as in OpenFst.
+#[derive(PartialOrd, PartialEq, Clone, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SetWeight<O: SetWeightOption = SetIntersectUnion> {
+    value: SetWeightVariant,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ghost: PhantomData<O>,
+}
+
+impl<O: SetWeightOption> SetWeight<O> {
+    /// Builds a finite set from a list of labels, in any order.
+    pub fn from_labels(mut labels: Vec<Label>) -> Self {
+        labels.sort_unstable();
+        labels.dedup();
+        Self::new(SetWeightVariant::Labels(labels))
+    }
+
+    pub fn empty_set() -> Self {
+        Self::new(SetWeightVariant::Labels(vec![]))
+    }
+
+    pub fn univ_set() -> Self {
+        Self::new(SetWeightVariant::Universal)
+    }
+
+    pub fn is_univ_set(&self) -> bool {
+        self.value == SetWeightVariant::Universal
+    }
+
+    /// Returns true if `label` belongs to the set.
+    pub fn contains(&self, label: Label) -> bool {
+        match &self.value {
+            SetWeightVariant::Universal => true,
+            SetWeightVariant::Labels(l) => l.binary_search(&label).is_ok(),
+        }
+    }
+}
+
+impl<O: SetWeightOption> AsRef<SetWeight<O>> for SetWeight<O> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<O: SetWeightOption> Semiring for SetWeight<O> {
+    type Type = SetWeightVariant;
+    type ReverseWeight = SetWeight<O>;
+
+    fn zero() -> Self {
+        if O::UNION_INTERSECT {
+            Self::empty_set()
+        } else {
+            Self::univ_set()
+        }
+    }
+
+    fn one() -> Self {
+        if O::UNION_INTERSECT {
+            Self::univ_set()
+        } else {
+            Self::empty_set()
+        }
+    }
+
+    fn new(value: <Self as Semiring>::Type) -> Self {
+        Self {
+            value,
+            ghost: PhantomData,
+        }
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        self.value = if O::UNION_INTERSECT {
+            self.value.union(&rhs.borrow().value)
+        } else {
+            self.value.intersect(&rhs.borrow().value)
+        };
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        self.value = if O::UNION_INTERSECT {
+            self.value.intersect(&rhs.borrow().value)
+        } else {
+            self.value.union(&rhs.borrow().value)
+        };
+        Ok(())
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        &self.value
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.value
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.value = value
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        Ok(self.clone())
+    }
+
+    fn properties() -> SemiringProperties {
+        SemiringProperties::LEFT_SEMIRING
+            | SemiringProperties::RIGHT_SEMIRING
+            | SemiringProperties::COMMUTATIVE
+            | SemiringProperties::IDEMPOTENT
+    }
+}
+
+impl<O: SetWeightOption> ReverseBack<SetWeight<O>> for SetWeight<O> {
+    fn reverse_back(&self) -> Result<SetWeight<O>> {
+        Ok(self.clone())
+    }
+}
+
+impl<O: SetWeightOption> WeaklyDivisibleSemiring for SetWeight<O> {
+    fn divide_assign(&mut self, rhs: &Self, _divide_type: DivideType) -> Result<()> {
+        if O::UNION_INTERSECT {
+            // Any superset of self works : the universal set if possible.
+            if self == rhs {
+                self.value = SetWeightVariant::Universal;
+            }
+        } else {
+            self.value = self.value.difference(&rhs.value)?;
+        }
+        Ok(())
+    }
+}
+
+impl<O: SetWeightOption> WeightQuantize for SetWeight<O> {
+    fn quantize_assign(&mut self, _delta: f32) -> Result<()> {
+        // Nothing to do
+        Ok(())
+    }
+}
+
+impl<O: SetWeightOption> Display for SetWeight<O> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.value {
+            SetWeightVariant::Universal => write!(f, "UnivSet")?,
+            SetWeightVariant::Labels(l) => {
+                if l.is_empty() {
+                    write!(f, "EmptySet")?;
+                } else {
+                    for (idx, label) in l.iter().enumerate() {
+                        if idx > 0 {
+                            write!(f, "_")?;
+                        }
+                        write!(f, "{}", label)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<O: SetWeightOption> SetWeight<O> {
+    fn parse_text_empty_set(i: &str) -> IResult<&str, Self> {
+        let (i, _) = tag("EmptySet")(i)?;
+        Ok((i, Self::empty_set()))
+    }
+
+    fn parse_text_univ_set(i: &str) -> IResult<&str, Self> {
+        let (i, _) = tag("UnivSet")(i)?;
+        Ok((i, Self::univ_set()))
+    }
+
+    fn parse_text_labels(i: &str) -> IResult<&str, Self> {
+        let (i, labels) = separated_list(tag("_"), num)(i)?;
+        Ok((i, Self::from_labels(labels)))
+    }
+}
+
+impl<O: SetWeightOption> SerializableSemiring for SetWeight<O> {
+    fn weight_type() -> String {
+        if O::UNION_INTERSECT {
+            "union_set".to_string()
+        } else {
+            "set".to_string()
+        }
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, n) = le_i32(i)?;
+        let (i, labels) = count(le_i32, n as usize)(i)?;
+        if labels == [SET_UNIV_LABEL] {
+            Ok((i, Self::univ_set()))
+        } else {
+            Ok((
+                i,
+                Self::from_labels(labels.into_iter().map(|l| l as Label).collect()),
+            ))
+        }
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        match &self.value {
+            SetWeightVariant::Universal => {
+                write_bin_i32(file, 1)?;
+                write_bin_i32(file, SET_UNIV_LABEL)?;
+            }
+            SetWeightVariant::Labels(l) => {
+                write_bin_i32(file, l.len() as i32)?;
+                for label in l.iter() {
+                    write_bin_i32(file, *label as i32)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        alt((
+            Self::parse_text_empty_set,
+            Self::parse_text_univ_set,
+            Self::parse_text_labels,
+        ))(i)
+    }
+}
+
+test_semiring_serializable!(
+    tests_set_weight_serializable,
+    SetWeight::<SetIntersectUnion>,
+    SetWeight::zero() SetWeight::one() SetWeight::from_labels(vec![3]) SetWeight::from_labels(vec![7, 1, 42])
+);
+
+test_semiring_serializable!(
+    tests_set_weight_union_intersect_serializable,
+    SetWeight::<SetUnionIntersect>,
+    SetWeight::zero() SetWeight::one() SetWeight::from_labels(vec![3]) SetWeight::from_labels(vec![7, 1, 42])
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::algorithms::determinize::{determinize, DeterminizeType};
+    use crate::algorithms::shortest_distance;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+    use crate::Tr;
+
+    #[test]
+    fn test_set_weight_intersect_union() -> Result<()> {
+        type W = SetWeight<SetIntersectUnion>;
+        let a = W::from_labels(vec![1, 2, 3]);
+        let b = W::from_labels(vec![3, 4]);
+        assert_eq!(a.plus(&b)?, W::from_labels(vec![3]));
+        assert_eq!(a.times(&b)?, W::from_labels(vec![1, 2, 3, 4]));
+        assert_eq!(a.plus(W::zero())?, a);
+        assert_eq!(a.times(W::one())?, a);
+        assert_eq!(a.times(W::zero())?, W::zero());
+        assert_eq!(
+            a.divide(&W::from_labels(vec![3]), DivideType::DivideAny)?,
+            W::from_labels(vec![1, 2])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_weight_union_intersect() -> Result<()> {
+        type W = SetWeight<SetUnionIntersect>;
+        let a = W::from_labels(vec![1, 2, 3]);
+        let b = W::from_labels(vec![3, 4]);
+        assert_eq!(a.plus(&b)?, W::from_labels(vec![1, 2, 3, 4]));
+        assert_eq!(a.times(&b)?, W::from_labels(vec![3]));
+        assert_eq!(a.plus(W::zero())?, a);
+        assert_eq!(a.times(W::one())?, a);
+        assert_eq!(a.times(W::zero())?, W::zero());
+        assert_eq!(a.divide(&a, DivideType::DivideAny)?, W::one());
+        assert_eq!(format!("{}", a), "1_2_3");
+        assert_eq!(W::weight_type(), "union_set");
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_weight_algorithms() -> Result<()> {
+        // Tags reaching each state : the union over all the paths.
+        type W = SetWeight<SetUnionIntersect>;
+        let mut fst = VectorFst::<W>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, W::from_labels(vec![10]), s1))?;
+        fst.add_tr(s0, Tr::new(1, 1, W::univ_set(), s1))?;
+        fst.add_tr(s1, Tr::new(2, 2, W::from_labels(vec![10, 20]), s2))?;
+        fst.set_final(s2, W::one())?;
+
+        let distances = shortest_distance(&fst, false)?;
+        assert_eq!(distances[s1], W::univ_set());
+        assert_eq!(distances[s2], W::from_labels(vec![10, 20]));
+
+        let det: VectorFst<W> = determinize(Arc::new(fst), DeterminizeType::DeterminizeFunctional)?;
+        assert_eq!(det.num_states(), 3);
+        let s1 = det.get_trs(det.start().unwrap())?[0].nextstate;
+        let s2 = det.get_trs(s1)?[0].nextstate;
+        let path_weight = det.get_trs(det.start().unwrap())?[0]
+            .weight
+            .times(&det.get_trs(s1)?[0].weight)?
+            .times(det.final_weight(s2)?.unwrap())?;
+        assert_eq!(path_weight, W::from_labels(vec![10, 20]));
+        Ok(())
+    }
+}
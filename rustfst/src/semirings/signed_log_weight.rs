@@ -0,0 +1,318 @@
+use std::borrow::Borrow;
+use std::f32;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+
+use anyhow::Result;
+use nom::IResult;
+
+use crate::semirings::{
+    DivideType, LogWeight, ProductWeight, ReverseBack, Semiring, SemiringProperties,
+    SerializableSemiring, TropicalWeight, WeaklyDivisibleSemiring, WeightQuantize,
+};
+
+/// Signed log semiring: (log(±e^-x ± e^-y), +, inf, 0) on signed values.
+/// A weight is a pair (sign, value) where the sign is stored as a `TropicalWeight` equal to
+/// 1.0 or -1.0 and the value is the negative log of the absolute value, stored as a `LogWeight`.
+/// This allows representing negative quantities and computing differences in the log domain.
+/// The binary and text representations are compatible with the `signed_log` weight of OpenFst.
+#[derive(Debug, PartialOrd, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SignedLogWeight(ProductWeight<TropicalWeight, LogWeight>);
+
+fn ln_pos_exp(x: f32) -> f32 {
+    ((-x).exp()).ln_1p()
+}
+
+fn ln_neg_exp(x: f32) -> f32 {
+    (-((-x).exp())).ln_1p()
+}
+
+fn sign_weight(positive: bool) -> TropicalWeight {
+    if positive {
+        TropicalWeight::new(1.0)
+    } else {
+        TropicalWeight::new(-1.0)
+    }
+}
+
+impl SignedLogWeight {
+    /// Creates a weight from its sign and the negative log of its absolute value.
+    pub fn new_signed(positive: bool, value: f32) -> Self {
+        Self::new((sign_weight(positive), LogWeight::new(value)))
+    }
+
+    /// Creates a weight representing the real number `x`.
+    pub fn from_real(x: f32) -> Self {
+        Self::new_signed(x >= 0.0, -x.abs().ln())
+    }
+
+    /// Returns the real number represented by this weight.
+    pub fn to_real(&self) -> f32 {
+        let abs = (-*self.value2().value()).exp();
+        if self.is_positive() {
+            abs
+        } else {
+            -abs
+        }
+    }
+
+    pub fn value1(&self) -> &TropicalWeight {
+        self.0.value1()
+    }
+
+    pub fn value2(&self) -> &LogWeight {
+        self.0.value2()
+    }
+
+    pub fn set_value1(&mut self, new_weight: TropicalWeight) {
+        self.0.set_value1(new_weight);
+    }
+
+    pub fn set_value2(&mut self, new_weight: LogWeight) {
+        self.0.set_value2(new_weight);
+    }
+
+    pub fn is_positive(&self) -> bool {
+        *self.value1().value() > 0.0
+    }
+
+    /// Returns `self - rhs`.
+    pub fn minus<P: Borrow<Self>>(&self, rhs: P) -> Result<Self> {
+        let rhs = rhs.borrow();
+        let neg_rhs = Self::new_signed(!rhs.is_positive(), *rhs.value2().value());
+        self.plus(neg_rhs)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.value2().value().eq(&f32::INFINITY)
+    }
+}
+
+impl Semiring for SignedLogWeight {
+    type Type = (TropicalWeight, LogWeight);
+    type ReverseWeight = SignedLogWeight;
+
+    fn zero() -> Self {
+        Self::new_signed(true, f32::INFINITY)
+    }
+
+    fn one() -> Self {
+        Self::new_signed(true, 0.0)
+    }
+
+    fn new(weight: <Self as Semiring>::Type) -> Self {
+        Self(ProductWeight::new(weight))
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        let s1 = self.is_positive();
+        let s2 = rhs.is_positive();
+        let f1 = *self.value2().value();
+        let f2 = *rhs.value2().value();
+        let same_sign = s1 == s2;
+        if f1.eq(&f32::INFINITY) {
+            *self = rhs.clone();
+        } else if f2.eq(&f32::INFINITY) {
+        } else if f1 == f2 {
+            if same_sign {
+                self.set_value2(LogWeight::new(f2 - f32::consts::LN_2));
+            } else {
+                *self = Self::zero();
+            }
+        } else if f1 > f2 {
+            if same_sign {
+                self.set_value2(LogWeight::new(f2 - ln_pos_exp(f1 - f2)));
+            } else {
+                *self = Self::new_signed(s2, f2 - ln_neg_exp(f1 - f2));
+            }
+        } else if same_sign {
+            self.set_value2(LogWeight::new(f1 - ln_pos_exp(f2 - f1)));
+        } else {
+            self.set_value2(LogWeight::new(f1 - ln_neg_exp(f2 - f1)));
+        }
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        let rhs = rhs.borrow();
+        let positive = self.is_positive() == rhs.is_positive();
+        self.set_value1(sign_weight(positive));
+        let mut value = *self.value2();
+        value.times_assign(rhs.value2())?;
+        self.set_value2(value);
+        Ok(())
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        self.0.value()
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.0.take_value()
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        self.0.set_value(value)
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        Ok(self.clone())
+    }
+
+    fn properties() -> SemiringProperties {
+        SemiringProperties::LEFT_SEMIRING
+            | SemiringProperties::RIGHT_SEMIRING
+            | SemiringProperties::COMMUTATIVE
+    }
+}
+
+impl ReverseBack<SignedLogWeight> for SignedLogWeight {
+    fn reverse_back(&self) -> Result<SignedLogWeight> {
+        Ok(self.clone())
+    }
+}
+
+impl AsRef<SignedLogWeight> for SignedLogWeight {
+    fn as_ref(&self) -> &SignedLogWeight {
+        self
+    }
+}
+
+impl Default for SignedLogWeight {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+// Zero has two representations (positive and negative) that must compare equal.
+impl PartialEq for SignedLogWeight {
+    fn eq(&self, other: &Self) -> bool {
+        if self.is_positive() == other.is_positive() {
+            self.value2() == other.value2()
+        } else {
+            self.is_zero() && other.is_zero()
+        }
+    }
+}
+
+impl Hash for SignedLogWeight {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.is_positive() || self.is_zero()).hash(state);
+        self.value2().hash(state);
+    }
+}
+
+impl WeaklyDivisibleSemiring for SignedLogWeight {
+    fn divide_assign(&mut self, rhs: &Self, _divide_type: DivideType) -> Result<()> {
+        if rhs.is_zero() {
+            bail!("Division by 0")
+        }
+        let positive = self.is_positive() == rhs.is_positive();
+        if self.is_zero() {
+            *self = Self::zero();
+        } else {
+            *self = Self::new_signed(positive, self.value2().value() - rhs.value2().value());
+        }
+        Ok(())
+    }
+}
+
+impl WeightQuantize for SignedLogWeight {
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        self.0.quantize_assign(delta)
+    }
+}
+
+impl fmt::Display for SignedLogWeight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl SerializableSemiring for SignedLogWeight {
+    fn weight_type() -> String {
+        "signed_log".to_string()
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, w) = ProductWeight::<TropicalWeight, LogWeight>::parse_binary(i)?;
+        Ok((i, Self(w)))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.0.write_binary(file)
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, w) = ProductWeight::<TropicalWeight, LogWeight>::parse_text(i)?;
+        Ok((i, Self(w)))
+    }
+}
+
+test_semiring_serializable!(
+    tests_signed_log_weight_serializable,
+    SignedLogWeight,
+    SignedLogWeight::one() SignedLogWeight::zero() SignedLogWeight::new_signed(true, 0.5) SignedLogWeight::new_signed(false, 1.7)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_approx_eq(w: &SignedLogWeight, x: f32) {
+        assert!((w.to_real() - x).abs() < 1e-5, "{} != {}", w.to_real(), x);
+    }
+
+    #[test]
+    fn test_signed_log_weight_plus() -> Result<()> {
+        let a = SignedLogWeight::from_real(0.75);
+        let b = SignedLogWeight::from_real(-0.25);
+        assert_approx_eq(&a.plus(&b)?, 0.5);
+        assert_approx_eq(&b.plus(&a)?, 0.5);
+        assert_approx_eq(&b.plus(&b)?, -0.5);
+        assert_approx_eq(&a.minus(&b)?, 1.0);
+        assert_approx_eq(&b.minus(&a)?, -1.0);
+        assert_eq!(a.minus(&a)?, SignedLogWeight::zero());
+        assert_eq!(a.plus(SignedLogWeight::zero())?, a);
+        assert_eq!(SignedLogWeight::zero().plus(&b)?, b);
+        assert_eq!(
+            SignedLogWeight::new_signed(false, f32::INFINITY),
+            SignedLogWeight::zero()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_log_weight_times_divide() -> Result<()> {
+        let a = SignedLogWeight::from_real(0.5);
+        let b = SignedLogWeight::from_real(-0.25);
+        let c = a.times(&b)?;
+        assert_approx_eq(&c, -0.125);
+        assert_approx_eq(&b.times(&b)?, 0.0625);
+        assert_approx_eq(&c.divide(&b, DivideType::DivideAny)?, 0.5);
+        assert_approx_eq(&c.divide(&a, DivideType::DivideAny)?, -0.25);
+        assert_eq!(a.times(SignedLogWeight::zero())?, SignedLogWeight::zero());
+        assert_eq!(b.times(SignedLogWeight::one())?, b);
+        assert!(a
+            .divide(&SignedLogWeight::zero(), DivideType::DivideAny)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_signed_log_weight_openfst_format() -> Result<()> {
+        let w = SignedLogWeight::new_signed(false, 1.5);
+        assert_eq!(format!("{}", w), "-1,1.5");
+        let mut buffer = vec![];
+        w.write_binary(&mut buffer)?;
+        let mut expected = vec![];
+        expected.extend_from_slice(&(-1.0f32).to_le_bytes());
+        expected.extend_from_slice(&1.5f32.to_le_bytes());
+        assert_eq!(buffer, expected);
+        Ok(())
+    }
+}
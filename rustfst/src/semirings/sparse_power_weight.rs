@@ -0,0 +1,395 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use anyhow::Result;
+use nom::bytes::complete::tag;
+use nom::character::complete::digit1;
+use nom::combinator::map_res;
+use nom::multi::{count, many0};
+use nom::number::complete::{le_i32, le_i64};
+use nom::sequence::{preceded, separated_pair};
+use nom::IResult;
+
+use crate::parsers::bin_fst::utils_serialization::{write_bin_i32, write_bin_i64};
+#[cfg(test)]
+use crate::semirings::LogWeight;
+use crate::semirings::{
+    DivideType, ReverseBack, ScalarTimes, Semiring, SemiringProperties, SerializableSemiring,
+    WeaklyDivisibleSemiring, WeightQuantize,
+};
+
+/// Sparse cartesian power semiring: W ^ n with n only known at runtime.
+/// The weight is stored as a default value and a list of (index, weight) pairs sorted by index
+/// for the components that differ from the default value. Plus, times and divide are computed
+/// componentwise. Zero is the vector whose components are all W::zero() and one the vector
+/// whose components are all W::one().
+#[derive(PartialOrd, PartialEq, Clone, Eq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct SparsePowerWeight<W: Semiring> {
+    value: (W, Vec<(usize, W)>),
+}
+
+impl<W: Semiring> AsRef<SparsePowerWeight<W>> for SparsePowerWeight<W> {
+    fn as_ref(&self) -> &Self {
+        self
+    }
+}
+
+impl<W: Semiring> SparsePowerWeight<W> {
+    /// Returns the value of the components not explicitly stored.
+    pub fn default_value(&self) -> &W {
+        &self.value.0
+    }
+
+    /// Returns the value of the component at `index`.
+    pub fn get(&self, index: usize) -> &W {
+        match self.position(index) {
+            Ok(pos) => &self.value.1[pos].1,
+            Err(_) => &self.value.0,
+        }
+    }
+
+    /// Sets the value of the component at `index`.
+    pub fn set(&mut self, index: usize, weight: W) {
+        match self.position(index) {
+            Ok(pos) => {
+                if weight == self.value.0 {
+                    self.value.1.remove(pos);
+                } else {
+                    self.value.1[pos].1 = weight;
+                }
+            }
+            Err(pos) => {
+                if weight != self.value.0 {
+                    self.value.1.insert(pos, (index, weight));
+                }
+            }
+        }
+    }
+
+    /// Number of components different from the default value.
+    pub fn len(&self) -> usize {
+        self.value.1.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.1.is_empty()
+    }
+
+    /// Iterates over the components different from the default value, sorted by index.
+    pub fn iter(&self) -> impl Iterator<Item = &(usize, W)> {
+        self.value.1.iter()
+    }
+
+    fn position(&self, index: usize) -> std::result::Result<usize, usize> {
+        self.value.1.binary_search_by(|(i, _)| i.cmp(&index))
+    }
+
+    /// Computes `f` on each pair of components of `self` and `rhs`.
+    fn map_componentwise<F>(&self, rhs: &Self, mut f: F) -> Result<Self>
+    where
+        F: FnMut(&W, &W) -> Result<W>,
+    {
+        let default = f(&self.value.0, &rhs.value.0)?;
+        let mut entries = Vec::with_capacity(self.len().max(rhs.len()));
+        let mut it1 = self.value.1.iter().peekable();
+        let mut it2 = rhs.value.1.iter().peekable();
+        loop {
+            let (index, w) = match (it1.peek(), it2.peek()) {
+                (None, None) => break,
+                (Some((i1, w1)), None) => {
+                    it1.next();
+                    (*i1, f(w1, &rhs.value.0)?)
+                }
+                (None, Some((i2, w2))) => {
+                    it2.next();
+                    (*i2, f(&self.value.0, w2)?)
+                }
+                (Some((i1, w1)), Some((i2, w2))) => match i1.cmp(i2) {
+                    Ordering::Less => {
+                        it1.next();
+                        (*i1, f(w1, &rhs.value.0)?)
+                    }
+                    Ordering::Greater => {
+                        it2.next();
+                        (*i2, f(&self.value.0, w2)?)
+                    }
+                    Ordering::Equal => {
+                        it1.next();
+                        it2.next();
+                        (*i1, f(w1, w2)?)
+                    }
+                },
+            };
+            if w != default {
+                entries.push((index, w));
+            }
+        }
+        Ok(Self {
+            value: (default, entries),
+        })
+    }
+
+    /// Computes `f` on each component of `self`.
+    fn map<F, W2>(&self, mut f: F) -> Result<SparsePowerWeight<W2>>
+    where
+        F: FnMut(&W) -> Result<W2>,
+        W2: Semiring,
+    {
+        let default = f(&self.value.0)?;
+        let mut entries = Vec::with_capacity(self.len());
+        for (index, w) in self.iter() {
+            let w = f(w)?;
+            if w != default {
+                entries.push((*index, w));
+            }
+        }
+        Ok(SparsePowerWeight {
+            value: (default, entries),
+        })
+    }
+}
+
+impl<W: Semiring> Semiring for SparsePowerWeight<W> {
+    type Type = (W, Vec<(usize, W)>);
+    type ReverseWeight = SparsePowerWeight<W::ReverseWeight>;
+
+    fn zero() -> Self {
+        Self {
+            value: (W::zero(), vec![]),
+        }
+    }
+
+    fn one() -> Self {
+        Self {
+            value: (W::one(), vec![]),
+        }
+    }
+
+    /// Builds the weight from the default value and the list of (index, weight) pairs.
+    /// The pairs are sorted by index, the ones equal to the default value are discarded and
+    /// the last one wins for duplicated indices.
+    fn new(value: <Self as Semiring>::Type) -> Self {
+        let (default, entries) = value;
+        let mut res = Self {
+            value: (default, Vec::with_capacity(entries.len())),
+        };
+        for (index, w) in entries {
+            res.set(index, w);
+        }
+        res
+    }
+
+    fn plus_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        *self = self.map_componentwise(rhs.borrow(), |w1, w2| w1.plus(w2))?;
+        Ok(())
+    }
+
+    fn times_assign<P: Borrow<Self>>(&mut self, rhs: P) -> Result<()> {
+        *self = self.map_componentwise(rhs.borrow(), |w1, w2| w1.times(w2))?;
+        Ok(())
+    }
+
+    fn value(&self) -> &<Self as Semiring>::Type {
+        &self.value
+    }
+
+    fn take_value(self) -> <Self as Semiring>::Type {
+        self.value
+    }
+
+    fn set_value(&mut self, value: <Self as Semiring>::Type) {
+        *self = Self::new(value);
+    }
+
+    fn reverse(&self) -> Result<Self::ReverseWeight> {
+        self.map(|w| w.reverse())
+    }
+
+    fn properties() -> SemiringProperties {
+        W::properties()
+            & (SemiringProperties::LEFT_SEMIRING
+                | SemiringProperties::RIGHT_SEMIRING
+                | SemiringProperties::COMMUTATIVE
+                | SemiringProperties::IDEMPOTENT)
+    }
+}
+
+impl<W: Semiring> ReverseBack<SparsePowerWeight<W>> for SparsePowerWeight<W::ReverseWeight> {
+    fn reverse_back(&self) -> Result<SparsePowerWeight<W>> {
+        self.map(|w| w.reverse_back())
+    }
+}
+
+impl<W: WeaklyDivisibleSemiring> WeaklyDivisibleSemiring for SparsePowerWeight<W> {
+    fn divide_assign(&mut self, rhs: &Self, divide_type: DivideType) -> Result<()> {
+        *self = self.map_componentwise(rhs, |w1, w2| w1.divide(w2, divide_type))?;
+        Ok(())
+    }
+}
+
+impl<W: WeightQuantize> WeightQuantize for SparsePowerWeight<W> {
+    fn quantize_assign(&mut self, delta: f32) -> Result<()> {
+        *self = self.map(|w| w.quantize(delta))?;
+        Ok(())
+    }
+}
+
+impl<W: Semiring> ScalarTimes<W> for SparsePowerWeight<W> {
+    fn scalar_times(&self, scalar: &W) -> Result<Self> {
+        self.map(|w| scalar.times(w))
+    }
+}
+
+impl<W: SerializableSemiring> Display for SparsePowerWeight<W> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.default_value())?;
+        for (index, w) in self.iter() {
+            write!(f, ",{},{}", index, w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: SerializableSemiring> SparsePowerWeight<W> {
+    fn parse_binary_entry(i: &[u8]) -> IResult<&[u8], (usize, W)> {
+        let (i, index) = le_i32(i)?;
+        let (i, w) = W::parse_binary(i)?;
+        Ok((i, (index as usize, w)))
+    }
+
+    fn parse_text_entry(i: &str) -> IResult<&str, (usize, W)> {
+        preceded(
+            tag(","),
+            separated_pair(
+                map_res(digit1, |s: &str| s.parse::<usize>()),
+                tag(","),
+                W::parse_text,
+            ),
+        )(i)
+    }
+}
+
+impl<W: SerializableSemiring> SerializableSemiring for SparsePowerWeight<W> {
+    fn weight_type() -> String {
+        format!("{}_sparse_power", W::weight_type())
+    }
+
+    fn parse_binary(i: &[u8]) -> IResult<&[u8], Self> {
+        let (i, default) = W::parse_binary(i)?;
+        let (i, n) = le_i64(i)?;
+        let (i, entries) = count(Self::parse_binary_entry, n as usize)(i)?;
+        Ok((i, Self::new((default, entries))))
+    }
+
+    fn write_binary<F: Write>(&self, file: &mut F) -> Result<()> {
+        self.default_value().write_binary(file)?;
+        write_bin_i64(file, self.len() as i64)?;
+        for (index, w) in self.iter() {
+            write_bin_i32(file, *index as i32)?;
+            w.write_binary(file)?;
+        }
+        Ok(())
+    }
+
+    fn parse_text(i: &str) -> IResult<&str, Self> {
+        let (i, default) = W::parse_text(i)?;
+        let (i, entries) = many0(Self::parse_text_entry)(i)?;
+        Ok((i, Self::new((default, entries))))
+    }
+}
+
+test_semiring_serializable!(
+    tests_sparse_power_weight_serializable,
+    SparsePowerWeight::<LogWeight>,
+    SparsePowerWeight::zero()
+    SparsePowerWeight::one()
+    SparsePowerWeight::new((LogWeight::zero(), vec![(3, LogWeight::new(0.5)), (1000, LogWeight::new(1.7))]))
+    SparsePowerWeight::new((LogWeight::one(), vec![(7, LogWeight::new(-2.0))]))
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::shortest_distance;
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::MutableFst;
+    use crate::semirings::{ExpectationWeight, ProbabilityWeight, TropicalWeight};
+    use crate::Tr;
+
+    type SparseWeight = SparsePowerWeight<TropicalWeight>;
+
+    fn w(entries: Vec<(usize, f32)>) -> SparseWeight {
+        SparsePowerWeight::new((
+            TropicalWeight::one(),
+            entries
+                .into_iter()
+                .map(|(i, v)| (i, TropicalWeight::new(v)))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn test_sparse_power_weight_operations() -> Result<()> {
+        let a = w(vec![(5, 1.0), (10000, 2.0)]);
+        let b = w(vec![(5, 3.0), (42, 4.0)]);
+        assert_eq!(a.times(&b)?, w(vec![(5, 4.0), (42, 4.0), (10000, 2.0)]));
+        assert_eq!(a.plus(&b)?, w(vec![(5, 1.0)]));
+        assert_eq!(a.times(&b)?.divide(&b, DivideType::DivideAny)?, a);
+        assert_eq!(a.times(SparseWeight::one())?, a);
+        assert_eq!(a.plus(SparseWeight::zero())?, a);
+        assert_eq!(a.times(SparseWeight::zero())?, SparseWeight::zero());
+        assert_eq!(a.get(10000), &TropicalWeight::new(2.0));
+        assert_eq!(a.get(3), &TropicalWeight::one());
+        assert_eq!(w(vec![(2, 0.0), (1, 3.0)]).len(), 1);
+        assert_eq!(SparseWeight::weight_type(), "tropical_sparse_power");
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_power_weight_shortest_distance() -> Result<()> {
+        type Weight = SparsePowerWeight<ProbabilityWeight>;
+        let feature = |index: usize, v: f32| -> Weight {
+            let mut w = Weight::one();
+            w.set(index, ProbabilityWeight::new(v));
+            w
+        };
+
+        let mut fst = VectorFst::<Weight>::new();
+        let s0 = fst.add_state();
+        let s1 = fst.add_state();
+        let s2 = fst.add_state();
+        fst.set_start(s0)?;
+        fst.add_tr(s0, Tr::new(1, 1, feature(2, 0.5), s1))?;
+        fst.add_tr(s0, Tr::new(2, 2, feature(1000, 0.25), s1))?;
+        fst.add_tr(s1, Tr::new(3, 3, feature(2, 0.5), s2))?;
+        fst.set_final(s2, Weight::one())?;
+
+        let distances = shortest_distance(&fst, false)?;
+        let total = &distances[s2];
+        assert_eq!(total.default_value(), &ProbabilityWeight::new(2.0));
+        assert_eq!(total.get(2), &ProbabilityWeight::new(0.75));
+        assert_eq!(total.get(1000), &ProbabilityWeight::new(1.25));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_power_weight_expectation() -> Result<()> {
+        type Weight = ExpectationWeight<ProbabilityWeight, SparsePowerWeight<ProbabilityWeight>>;
+        let w = |p: f32, index: usize| -> Weight {
+            let mut features = SparsePowerWeight::zero();
+            features.set(index, ProbabilityWeight::new(p));
+            ExpectationWeight::new((ProbabilityWeight::new(p), features))
+        };
+        let res = w(0.5, 1).times(w(0.25, 2))?;
+        assert_eq!(res.value1(), &ProbabilityWeight::new(0.125));
+        assert_eq!(res.value2().get(1), &ProbabilityWeight::new(0.125));
+        assert_eq!(res.value2().get(2), &ProbabilityWeight::new(0.125));
+        assert_eq!(res.value2().get(3), &ProbabilityWeight::zero());
+        Ok(())
+    }
+}
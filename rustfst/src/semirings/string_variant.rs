@@ -1,6 +1,8 @@
 use crate::Label;
 
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
 pub enum StringWeightVariant {
     Infinity,
     Labels(Vec<Label>),
@@ -20,18 +20,24 @@ use crate::Label;
 
 /// String semiring: (identity, ., Infinity, Epsilon)
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct StringWeightRestrict {
     pub(crate) value: StringWeightVariant,
 }
 
 /// String semiring: (longest_common_prefix, ., Infinity, Epsilon)
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct StringWeightLeft {
     pub(crate) value: StringWeightVariant,
 }
 
 /// String semiring: (longest_common_suffix, ., Infinity, Epsilon)
 #[derive(Clone, Debug, PartialOrd, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct StringWeightRight {
     pub(crate) value: StringWeightVariant,
 }
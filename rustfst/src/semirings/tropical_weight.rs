@@ -122,6 +122,8 @@ impl WeaklyDivisibleSemiring for TropicalWeight {
 
 impl_quantize_f32!(TropicalWeight);
 
+impl_serde_f32!(TropicalWeight);
+
 partial_eq_and_hash_f32!(TropicalWeight);
 
 impl SerializableSemiring for TropicalWeight {
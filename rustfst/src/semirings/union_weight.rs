@@ -31,8 +31,11 @@ pub trait UnionWeightOption<W: Semiring>:
 /// for Plus() and Zero(), respectively. Template argument O specifies the union
 /// weight options as above.
 #[derive(PartialOrd, PartialEq, Clone, Eq, Debug, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct UnionWeight<W: Semiring, O: UnionWeightOption<W>> {
     pub(crate) list: Vec<W>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     ghost: PhantomData<O>,
 }
 
@@ -298,6 +298,38 @@ impl fmt::Display for SymbolTable {
     }
 }
 
+/// Serialized as a list of `[label, symbol]` pairs sorted by label.
+#[cfg(feature = "serde")]
+impl serde::Serialize for SymbolTable {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().sorted_by_key(|k| k.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SymbolTable {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let pairs = <Vec<(Label, Symbol)> as serde::Deserialize>::deserialize(deserializer)?;
+        let mut symt = SymbolTable::empty();
+        for (label, symbol) in pairs {
+            if symt.contains_label(label) || symt.contains_symbol(&symbol) {
+                return Err(serde::de::Error::custom(format!(
+                    "Duplicated entry in SymbolTable : {} {}",
+                    label, symbol
+                )));
+            }
+            symt.label_to_symbol.insert(label, symbol.clone());
+            symt.symbol_to_label.insert(symbol, label);
+        }
+        Ok(symt)
+    }
+}
+
 /// Creates a `SymbolTable` containing the arguments.
 /// ```
 /// # #[macro_use] extern crate rustfst; fn main() {
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use crate::algorithms::compose::compose;
+use crate::algorithms::tr_compares::{ilabel_compare, olabel_compare};
+use crate::algorithms::tr_mappers::{InputEpsilonMapper, OutputEpsilonMapper};
+use crate::algorithms::{fst_convert_from_ref, tr_map, tr_sort};
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{ExpandedFst, MutableFst};
+use crate::semirings::Semiring;
+
+/// Builds the cross-product transducer of two acceptors : it maps every string accepted by
+/// `input_fst` to every string accepted by `output_fst`. The weight of a pair is the product
+/// of the weights of both strings.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::{acceptor, cross};
+/// # fn main() -> Result<()> {
+/// let input: VectorFst<TropicalWeight> = acceptor(&[1, 2], TropicalWeight::new(1.0));
+/// let output: VectorFst<TropicalWeight> = acceptor(&[3], TropicalWeight::new(2.0));
+///
+/// let fst: VectorFst<TropicalWeight> = cross(&input, &output)?;
+///
+/// let path = fst.paths_iter().next().unwrap();
+/// assert_eq!(path.ilabels, vec![1, 2]);
+/// assert_eq!(path.olabels, vec![3]);
+/// assert_eq!(path.weight, TropicalWeight::new(3.0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn cross<W, F1, F2, F3>(input_fst: &F1, output_fst: &F2) -> Result<F3>
+where
+    W: Semiring,
+    F1: ExpandedFst<W>,
+    F2: ExpandedFst<W>,
+    F3: MutableFst<W>,
+{
+    if !input_fst.is_acceptor() || !output_fst.is_acceptor() {
+        bail!("cross : both FSTs must be acceptors");
+    }
+    let mut fst1: VectorFst<W> = fst_convert_from_ref(input_fst);
+    tr_map(&mut fst1, &OutputEpsilonMapper {})?;
+    tr_sort(&mut fst1, olabel_compare);
+    let mut fst2: VectorFst<W> = fst_convert_from_ref(output_fst);
+    tr_map(&mut fst2, &InputEpsilonMapper {})?;
+    tr_sort(&mut fst2, ilabel_compare);
+    compose(Arc::new(fst1), Arc::new(fst2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::algorithms::union::union;
+    use crate::fst_traits::PathsIterator;
+    use crate::semirings::ProbabilityWeight;
+    use crate::utils::{acceptor, transducer};
+
+    #[test]
+    fn test_cross_languages() -> Result<()> {
+        let mut input: VectorFst<ProbabilityWeight> = acceptor(&[1], ProbabilityWeight::new(0.5));
+        union(
+            &mut input,
+            &acceptor::<_, VectorFst<_>>(&[2, 2], ProbabilityWeight::new(0.5)),
+        )?;
+        let output: VectorFst<ProbabilityWeight> = acceptor(&[], ProbabilityWeight::new(0.2));
+
+        let fst: VectorFst<ProbabilityWeight> = cross(&input, &output)?;
+        let mut paths: Vec<_> = fst.paths_iter().collect();
+        paths.sort_by(|a, b| a.ilabels.cmp(&b.ilabels));
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].ilabels, vec![1]);
+        assert_eq!(paths[1].ilabels, vec![2, 2]);
+        for path in paths {
+            assert!(path.olabels.is_empty());
+            assert!((*path.weight.value() - 0.1).abs() < 1e-6);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cross_not_acceptor() {
+        let fst: VectorFst<ProbabilityWeight> = transducer(&[1], &[2], ProbabilityWeight::one());
+        assert!(cross::<_, _, _, VectorFst<_>>(&fst, &fst).is_err());
+    }
+}
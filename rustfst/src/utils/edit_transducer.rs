@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+
+use crate::algorithms::compose::compose_filters::SequenceComposeFilterBuilder;
+use crate::algorithms::compose::matchers::GenericMatcher;
+use crate::algorithms::compose::{compose, ComposeFst};
+use crate::algorithms::shortest_distance;
+use crate::algorithms::tr_compares::{ilabel_compare, olabel_compare};
+use crate::algorithms::tr_sort;
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::{CoreFst, ExpandedFst, MutableFst};
+use crate::semirings::Semiring;
+use crate::{Label, Tr, EPS_LABEL};
+
+/// Lazy composition of an input string with the left factor and of the right factor with an
+/// output string, whose paths are the alignments of the two strings.
+pub type EditLattice<W> = ComposeFst<
+    W,
+    SequenceComposeFilterBuilder<
+        W,
+        GenericMatcher<W, VectorFst<W>>,
+        GenericMatcher<W, VectorFst<W>>,
+    >,
+>;
+
+/// Factored transducer computing the weighted edit distance between strings over an alphabet.
+///
+/// The edit transducer is the composition of two factors, each with a single state and
+/// `O(|alphabet|)` trs. The left factor maps each label to itself with a weight of `one`, to a
+/// deletion marker with the `delete_cost`, to a substitution marker with the `substitute_cost`
+/// and inserts an insertion marker with the `insert_cost`. The right factor maps each label to
+/// itself, removes the deletion marker and maps the substitution and insertion markers back to
+/// every label. The markers are the three labels following the largest label of the alphabet.
+///
+/// The factors are never composed together : the input string is composed with the left factor,
+/// the right factor with the output string, and both results are composed lazily, so that the
+/// lattice only contains the alignments of the two strings.
+///
+/// If `transpose_cost` is set, swapping two adjacent labels (`ab:ba`) is allowed as well. The
+/// left factor then remembers the first label of the swap, which adds `2 * |alphabet|` states
+/// and `|alphabet|²` trs to it.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use std::sync::Arc;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::{acceptor, EditTransducer};
+/// # fn main() -> Result<()> {
+/// let cost = TropicalWeight::new(1.0);
+/// let edit = EditTransducer::new(&[1, 2, 3], cost, cost, cost, None)?;
+///
+/// let fst1: VectorFst<TropicalWeight> = acceptor(&[1, 2, 3], TropicalWeight::one());
+/// let fst2: VectorFst<TropicalWeight> = acceptor(&[2, 3, 3], TropicalWeight::one());
+///
+/// // Delete 1 and insert 3.
+/// let distance = edit.distance(Arc::new(fst1), Arc::new(fst2))?;
+/// assert_eq!(distance, Some(TropicalWeight::new(2.0)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditTransducer<W: Semiring> {
+    left_factor: Arc<VectorFst<W>>,
+    right_factor: Arc<VectorFst<W>>,
+}
+
+impl<W: Semiring> EditTransducer<W> {
+    pub fn new(
+        alphabet: &[Label],
+        insert_cost: W,
+        delete_cost: W,
+        substitute_cost: W,
+        transpose_cost: Option<W>,
+    ) -> Result<Self> {
+        if alphabet.contains(&EPS_LABEL) {
+            bail!("EditTransducer : the alphabet can't contain the epsilon label");
+        }
+        let mut alphabet = alphabet.to_vec();
+        alphabet.sort();
+        alphabet.dedup();
+        let max_label = alphabet.last().cloned().unwrap_or(EPS_LABEL);
+        let insert_label = max_label + 1;
+        let delete_label = max_label + 2;
+        let substitute_label = max_label + 3;
+
+        let mut left_factor = VectorFst::new();
+        let s = left_factor.add_state();
+        left_factor.set_start(s)?;
+        left_factor.set_final(s, W::one())?;
+        left_factor.add_tr(s, Tr::new(EPS_LABEL, insert_label, insert_cost, s))?;
+        for &a in &alphabet {
+            left_factor.add_tr(s, Tr::new(a, a, W::one(), s))?;
+            left_factor.add_tr(s, Tr::new(a, delete_label, delete_cost.clone(), s))?;
+            left_factor.add_tr(s, Tr::new(a, substitute_label, substitute_cost.clone(), s))?;
+        }
+
+        if let Some(transpose_cost) = transpose_cost {
+            // Reading `ab` writes `ba` : the first label is written back after the second one.
+            for &a in &alphabet {
+                let pending = left_factor.add_state();
+                let swapped = left_factor.add_state();
+                left_factor.add_tr(s, Tr::new(a, EPS_LABEL, transpose_cost.clone(), pending))?;
+                for &b in alphabet.iter().filter(|b| **b != a) {
+                    left_factor.add_tr(pending, Tr::new(b, b, W::one(), swapped))?;
+                }
+                left_factor.add_tr(swapped, Tr::new(EPS_LABEL, a, W::one(), s))?;
+            }
+        }
+
+        let mut right_factor = VectorFst::new();
+        let s = right_factor.add_state();
+        right_factor.set_start(s)?;
+        right_factor.set_final(s, W::one())?;
+        right_factor.add_tr(s, Tr::new(delete_label, EPS_LABEL, W::one(), s))?;
+        for &a in &alphabet {
+            right_factor.add_tr(s, Tr::new(a, a, W::one(), s))?;
+            right_factor.add_tr(s, Tr::new(insert_label, a, W::one(), s))?;
+            right_factor.add_tr(s, Tr::new(substitute_label, a, W::one(), s))?;
+        }
+
+        tr_sort(&mut left_factor, ilabel_compare);
+        tr_sort(&mut right_factor, olabel_compare);
+        Ok(Self {
+            left_factor: Arc::new(left_factor),
+            right_factor: Arc::new(right_factor),
+        })
+    }
+
+    /// Factor mapping the labels to themselves or to the edit markers. Its trs are sorted on
+    /// the input labels.
+    pub fn left_factor(&self) -> &Arc<VectorFst<W>> {
+        &self.left_factor
+    }
+
+    /// Factor mapping the labels and the edit markers back to labels. Its trs are sorted on the
+    /// output labels.
+    pub fn right_factor(&self) -> &Arc<VectorFst<W>> {
+        &self.right_factor
+    }
+
+    /// Builds the lattice of the alignments of `input` with `output`. The composition of the two
+    /// factors isn't computed, the expensive composition is done lazily.
+    pub fn lattice<F1: ExpandedFst<W>, F2: ExpandedFst<W>>(
+        &self,
+        input: Arc<F1>,
+        output: Arc<F2>,
+    ) -> Result<EditLattice<W>> {
+        let mut left: VectorFst<W> = compose(input, Arc::clone(&self.left_factor))?;
+        tr_sort(&mut left, olabel_compare);
+        let mut right: VectorFst<W> = compose(Arc::clone(&self.right_factor), output)?;
+        tr_sort(&mut right, ilabel_compare);
+        ComposeFst::new_auto(Arc::new(left), Arc::new(right))
+    }
+
+    /// Computes the sum of the weights of the alignments of `input` with `output`, i.e the edit
+    /// distance for weights with the path property like `TropicalWeight`. Returns `None` if the
+    /// strings can't be aligned, e.g when they contain labels outside of the alphabet.
+    pub fn distance<F1: ExpandedFst<W>, F2: ExpandedFst<W>>(
+        &self,
+        input: Arc<F1>,
+        output: Arc<F2>,
+    ) -> Result<Option<W>> {
+        let lattice = self.lattice(input, output)?;
+        let distances = shortest_distance(&lattice, false)?;
+        let mut res: Option<W> = None;
+        for (s, d) in distances.iter().enumerate() {
+            if let Some(final_weight) = lattice.final_weight(s)? {
+                let w = d.times(final_weight)?;
+                res = Some(match res {
+                    Some(r) => r.plus(w)?,
+                    None => w,
+                });
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_traits::StateIterator;
+    use crate::semirings::TropicalWeight;
+    use crate::utils::acceptor;
+
+    fn distance(
+        edit: &EditTransducer<TropicalWeight>,
+        s1: &[Label],
+        s2: &[Label],
+    ) -> Result<Option<TropicalWeight>> {
+        let fst1: VectorFst<TropicalWeight> = acceptor(s1, TropicalWeight::one());
+        let fst2: VectorFst<TropicalWeight> = acceptor(s2, TropicalWeight::one());
+        edit.distance(Arc::new(fst1), Arc::new(fst2))
+    }
+
+    #[test]
+    fn test_edit_transducer_costs() -> Result<()> {
+        let edit = EditTransducer::new(
+            &[1, 2, 3],
+            TropicalWeight::new(1.0),
+            TropicalWeight::new(2.0),
+            TropicalWeight::new(5.0),
+            None,
+        )?;
+        assert_eq!(
+            distance(&edit, &[1, 2], &[1, 2])?,
+            Some(TropicalWeight::one())
+        );
+        assert_eq!(
+            distance(&edit, &[1], &[1, 3])?,
+            Some(TropicalWeight::new(1.0))
+        );
+        assert_eq!(
+            distance(&edit, &[1, 3], &[1])?,
+            Some(TropicalWeight::new(2.0))
+        );
+        // A deletion followed by an insertion is cheaper than a substitution.
+        assert_eq!(distance(&edit, &[1], &[2])?, Some(TropicalWeight::new(3.0)));
+        assert_eq!(distance(&edit, &[1], &[4])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_transducer_factors_size() -> Result<()> {
+        let alphabet: Vec<Label> = (1..=100).collect();
+        let cost = TropicalWeight::new(1.0);
+        let edit = EditTransducer::new(&alphabet, cost, cost, cost, None)?;
+        assert_eq!(edit.left_factor().num_states(), 1);
+        assert_eq!(edit.left_factor().num_trs(0)?, 3 * 100 + 1);
+        assert_eq!(edit.right_factor().num_states(), 1);
+        assert_eq!(edit.right_factor().num_trs(0)?, 3 * 100 + 1);
+
+        // Only the alignments of the two strings are computed.
+        let fst1: VectorFst<TropicalWeight> = acceptor(&[1, 2], TropicalWeight::one());
+        let fst2: VectorFst<TropicalWeight> = acceptor(&[2, 3], TropicalWeight::one());
+        let lattice = edit.lattice(Arc::new(fst1), Arc::new(fst2))?;
+        assert!(lattice.states_iter().count() < 100);
+        assert_eq!(
+            distance(&edit, &[1, 2], &[2, 3])?,
+            Some(TropicalWeight::new(2.0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_transducer_transpositions() -> Result<()> {
+        let cost = TropicalWeight::new(1.0);
+        let edit = EditTransducer::new(&[1, 2, 3], cost, cost, cost, None)?;
+        assert_eq!(
+            distance(&edit, &[1, 2, 3], &[2, 1, 3])?,
+            Some(TropicalWeight::new(2.0))
+        );
+        let edit = EditTransducer::new(&[1, 2, 3], cost, cost, cost, Some(cost))?;
+        assert_eq!(
+            distance(&edit, &[1, 2, 3], &[2, 1, 3])?,
+            Some(TropicalWeight::new(1.0))
+        );
+        assert_eq!(
+            distance(&edit, &[1, 1], &[1, 1])?,
+            Some(TropicalWeight::one())
+        );
+        assert_eq!(edit.left_factor().num_states(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_transducer_epsilon() {
+        let one = TropicalWeight::one();
+        assert!(EditTransducer::new(&[0, 1], one, one, one, None).is_err());
+    }
+}
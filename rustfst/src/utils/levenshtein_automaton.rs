@@ -0,0 +1,277 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::{bail, Result};
+
+use crate::algorithms::minimize;
+use crate::fst_traits::{AllocableFst, ExpandedFst, MutableFst};
+use crate::semirings::{WeaklyDivisibleSemiring, WeightQuantize};
+use crate::{Label, StateId, Tr, EPS_LABEL};
+
+/// Position in the word, number of edits and whether a transposition is in progress.
+type LevenshteinPosition = (usize, usize, bool);
+
+/// Builds the minimal deterministic acceptor recognizing all the strings over `alphabet`
+/// within an edit distance of `max_distance` from `word`. Insertions, deletions and
+/// substitutions count as one edit, as do transpositions of two adjacent labels if
+/// `transpositions` is true. The labels of `word` are always part of the alphabet.
+///
+/// The acceptor is built directly by a subset construction over the positions in the word,
+/// its size doesn't depend on any vocabulary. Composing it with a lexicon yields the entries
+/// close to `word`.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::semirings::TropicalWeight;
+/// # use rustfst::utils::levenshtein_automaton;
+/// # fn main() -> Result<()> {
+/// let fst: VectorFst<TropicalWeight> = levenshtein_automaton(&[1, 2], &[1, 2], 1, false)?;
+///
+/// let mut strings: Vec<_> = fst.paths_iter().map(|p| p.ilabels).collect();
+/// strings.sort();
+/// assert_eq!(strings.len(), 9);
+/// assert_eq!(strings[..4], [vec![1], vec![1, 1], vec![1, 1, 2], vec![1, 2]]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn levenshtein_automaton<W, F>(
+    word: &[Label],
+    alphabet: &[Label],
+    max_distance: usize,
+    transpositions: bool,
+) -> Result<F>
+where
+    W: WeaklyDivisibleSemiring + WeightQuantize,
+    F: MutableFst<W> + ExpandedFst<W> + AllocableFst<W>,
+{
+    if word.contains(&EPS_LABEL) || alphabet.contains(&EPS_LABEL) {
+        bail!("levenshtein_automaton : the word and the alphabet can't contain the epsilon label");
+    }
+    let builder = LevenshteinBuilder {
+        word,
+        max_distance,
+        transpositions,
+    };
+    let mut word_labels = word.to_vec();
+    word_labels.sort();
+    word_labels.dedup();
+    let mut alphabet = alphabet.to_vec();
+    alphabet.extend_from_slice(word);
+    alphabet.sort();
+    alphabet.dedup();
+
+    let mut fst = F::new();
+    let mut states: HashMap<Vec<LevenshteinPosition>, StateId> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    let start_positions = builder.close(vec![(0, 0, false)]);
+    let start = fst.add_state();
+    fst.set_start(start)?;
+    states.insert(start_positions.clone(), start);
+    queue.push_back((start_positions, start));
+
+    while let Some((positions, state)) = queue.pop_front() {
+        if builder.is_final(&positions) {
+            fst.set_final(state, W::one())?;
+        }
+        // All the labels missing from the word lead to the same positions.
+        let other_positions = builder.next(&positions, None);
+        for &label in &alphabet {
+            let next_positions = if word_labels.binary_search(&label).is_ok() {
+                builder.next(&positions, Some(label))
+            } else {
+                other_positions.clone()
+            };
+            if next_positions.is_empty() {
+                continue;
+            }
+            let next_state = match states.entry(next_positions) {
+                Entry::Occupied(e) => *e.get(),
+                Entry::Vacant(e) => {
+                    let next_state = fst.add_state();
+                    queue.push_back((e.key().clone(), next_state));
+                    e.insert(next_state);
+                    next_state
+                }
+            };
+            fst.add_tr(state, Tr::new(label, label, W::one(), next_state))?;
+        }
+    }
+
+    minimize(&mut fst, true)?;
+    Ok(fst)
+}
+
+struct LevenshteinBuilder<'a> {
+    word: &'a [Label],
+    max_distance: usize,
+    transpositions: bool,
+}
+
+impl<'a> LevenshteinBuilder<'a> {
+    fn is_final(&self, positions: &[LevenshteinPosition]) -> bool {
+        positions
+            .iter()
+            .any(|(i, _, transposing)| *i == self.word.len() && !transposing)
+    }
+
+    /// Positions reached by reading `label` from `positions`. `None` stands for any label
+    /// missing from the word.
+    fn next(
+        &self,
+        positions: &[LevenshteinPosition],
+        label: Option<Label>,
+    ) -> Vec<LevenshteinPosition> {
+        let n = self.word.len();
+        let matches = |i: usize| i < n && Some(self.word[i]) == label;
+        let mut next_positions = vec![];
+        for &(i, e, transposing) in positions {
+            if transposing {
+                if matches(i) {
+                    next_positions.push((i + 2, e, false));
+                }
+                continue;
+            }
+            if matches(i) {
+                next_positions.push((i + 1, e, false));
+            }
+            if e < self.max_distance {
+                // Insertion and substitution.
+                next_positions.push((i, e + 1, false));
+                if i < n {
+                    next_positions.push((i + 1, e + 1, false));
+                }
+                if self.transpositions && matches(i + 1) && !matches(i) {
+                    next_positions.push((i, e + 1, true));
+                }
+            }
+        }
+        self.close(next_positions)
+    }
+
+    /// Adds the positions reachable through deletions and only keeps the smallest number
+    /// of edits for each position.
+    fn close(&self, mut positions: Vec<LevenshteinPosition>) -> Vec<LevenshteinPosition> {
+        let mut idx = 0;
+        while idx < positions.len() {
+            let (i, e, transposing) = positions[idx];
+            if !transposing && i < self.word.len() && e < self.max_distance {
+                positions.push((i + 1, e + 1, false));
+            }
+            idx += 1;
+        }
+        positions.sort_by_key(|(i, e, transposing)| (*i, *transposing, *e));
+        positions.dedup_by_key(|(i, _, transposing)| (*i, *transposing));
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_impls::VectorFst;
+    use crate::fst_properties::FstProperties;
+    use crate::fst_traits::CoreFst;
+    use crate::semirings::TropicalWeight;
+    use crate::trs::Trs;
+
+    fn accepts(fst: &VectorFst<TropicalWeight>, labels: &[Label]) -> Result<bool> {
+        let mut state = fst.start().unwrap();
+        for label in labels {
+            let trs = fst.get_trs(state)?;
+            match trs.trs().iter().find(|tr| tr.ilabel == *label) {
+                Some(tr) => state = tr.nextstate,
+                None => return Ok(false),
+            }
+        }
+        fst.is_final(state)
+    }
+
+    /// Optimal string alignment distance.
+    fn distance(s1: &[Label], s2: &[Label], transpositions: bool) -> usize {
+        let mut d = vec![vec![0; s2.len() + 1]; s1.len() + 1];
+        for i in 0..=s1.len() {
+            for j in 0..=s2.len() {
+                d[i][j] = if i == 0 || j == 0 {
+                    i + j
+                } else {
+                    let cost = if s1[i - 1] == s2[j - 1] { 0 } else { 1 };
+                    let mut v = (d[i - 1][j] + 1)
+                        .min(d[i][j - 1] + 1)
+                        .min(d[i - 1][j - 1] + cost);
+                    if transpositions
+                        && i > 1
+                        && j > 1
+                        && s1[i - 1] == s2[j - 2]
+                        && s1[i - 2] == s2[j - 1]
+                    {
+                        v = v.min(d[i - 2][j - 2] + 1);
+                    }
+                    v
+                };
+            }
+        }
+        d[s1.len()][s2.len()]
+    }
+
+    fn all_strings(alphabet: &[Label], max_len: usize) -> Vec<Vec<Label>> {
+        let mut strings = vec![vec![]];
+        let mut last = vec![vec![]];
+        for _ in 0..max_len {
+            last = last
+                .iter()
+                .flat_map(|s: &Vec<Label>| {
+                    alphabet.iter().map(move |l| {
+                        let mut s = s.clone();
+                        s.push(*l);
+                        s
+                    })
+                })
+                .collect();
+            strings.extend(last.iter().cloned());
+        }
+        strings
+    }
+
+    #[test]
+    fn test_levenshtein_automaton_exhaustive() -> Result<()> {
+        let word = vec![1, 2, 3, 1];
+        for &transpositions in &[false, true] {
+            for max_distance in 0..3 {
+                let fst: VectorFst<TropicalWeight> =
+                    levenshtein_automaton(&word, &[4], max_distance, transpositions)?;
+                assert!(fst
+                    .properties()?
+                    .contains(FstProperties::I_DETERMINISTIC | FstProperties::ACYCLIC));
+                for s in all_strings(&[1, 2, 3, 4], 6) {
+                    assert_eq!(
+                        accepts(&fst, &s)?,
+                        distance(&word, &s, transpositions) <= max_distance,
+                        "{:?} {} {}",
+                        s,
+                        max_distance,
+                        transpositions
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_levenshtein_automaton_empty_word() -> Result<()> {
+        let fst: VectorFst<TropicalWeight> = levenshtein_automaton(&[], &[1, 2], 1, false)?;
+        assert!(accepts(&fst, &[])?);
+        assert!(accepts(&fst, &[2])?);
+        assert!(!accepts(&fst, &[1, 1])?);
+        assert!(
+            levenshtein_automaton::<TropicalWeight, VectorFst<_>>(&[0], &[1], 1, false).is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::fst_impls::VectorFst;
+use crate::fst_traits::MutableFst;
+use crate::semirings::{DivideType, WeaklyDivisibleSemiring};
+use crate::{Label, StateId, Tr, EPS_LABEL};
+
+/// State on the path of the last inserted key. Its last tr, if any, leads to the next
+/// unfinished state and its `nextstate` is only set when that state gets frozen.
+#[derive(Debug, Clone)]
+struct UnfinishedState<W> {
+    final_weight: Option<W>,
+    trs: Vec<Tr<W>>,
+}
+
+impl<W: WeaklyDivisibleSemiring> UnfinishedState<W> {
+    fn new() -> Self {
+        Self {
+            final_weight: None,
+            trs: vec![],
+        }
+    }
+
+    /// Multiplies on the left the weights of all the paths leaving the state.
+    fn premultiply(&mut self, weight: &W) -> Result<()> {
+        if let Some(final_weight) = self.final_weight.as_mut() {
+            *final_weight = weight.times(&*final_weight)?;
+        }
+        for tr in self.trs.iter_mut() {
+            tr.weight = weight.times(&tr.weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the minimal deterministic acyclic acceptor of a set of weighted keys inserted in
+/// lexicographic order, following the incremental construction of Daciuk et al.
+///
+/// Only the states on the path of the last inserted key are kept in memory, the other ones
+/// are frozen : they are added to the output FST, merged with an equivalent state if one
+/// already exists. The weights are pushed towards the initial state along the common prefixes
+/// of the keys so that the suffixes with the same weights can be shared. The construction is
+/// linear in the size of the keys, contrary to a `union` of linear acceptors followed by
+/// `determinize` and `minimize`.
+///
+/// A transducer can be built by inserting keys made of pairs of labels encoded with an
+/// `EncodeTable`, sorted after encoding, and by calling `decode` on the result.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_impls::ConstFst;
+/// # use rustfst::fst_traits::{ExpandedFst, PathsIterator};
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::MinimalAcyclicBuilder;
+/// # fn main() -> Result<()> {
+/// let mut builder = MinimalAcyclicBuilder::new();
+/// builder.insert(&[1, 2, 3], TropicalWeight::new(1.0))?;
+/// builder.insert(&[1, 4, 3], TropicalWeight::new(1.0))?;
+/// builder.insert(&[2], TropicalWeight::new(2.0))?;
+/// let fst: ConstFst<TropicalWeight> = builder.finish()?;
+///
+/// assert_eq!(fst.num_states(), 4);
+/// let mut paths: Vec<_> = fst.paths_iter().map(|p| (p.ilabels, p.weight)).collect();
+/// paths.sort_by(|a, b| a.0.cmp(&b.0));
+/// assert_eq!(paths[0], (vec![1, 2, 3], TropicalWeight::new(1.0)));
+/// assert_eq!(paths[2], (vec![2], TropicalWeight::new(2.0)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinimalAcyclicBuilder<W: WeaklyDivisibleSemiring> {
+    fst: VectorFst<W>,
+    /// States on the path of the last inserted key, indexed by their depth.
+    unfinished: Vec<UnfinishedState<W>>,
+    /// Frozen states indexed by their final weight and trs.
+    registry: HashMap<(Option<W>, Vec<Tr<W>>), StateId>,
+    last_key: Option<Vec<Label>>,
+}
+
+impl<W: WeaklyDivisibleSemiring> Default for MinimalAcyclicBuilder<W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: WeaklyDivisibleSemiring> MinimalAcyclicBuilder<W> {
+    pub fn new() -> Self {
+        Self {
+            fst: VectorFst::new(),
+            unfinished: vec![UnfinishedState::new()],
+            registry: HashMap::new(),
+            last_key: None,
+        }
+    }
+
+    /// Adds a key to the acceptor. The keys must be inserted in strictly increasing
+    /// lexicographic order and can't contain the epsilon label.
+    pub fn insert(&mut self, key: &[Label], weight: W) -> Result<()> {
+        if key.contains(&EPS_LABEL) {
+            bail!("MinimalAcyclicBuilder : keys can't contain the epsilon label");
+        }
+        if weight == W::zero() {
+            bail!("MinimalAcyclicBuilder : weights can't be zero");
+        }
+        let prefix_len = match &self.last_key {
+            Some(last_key) => {
+                if key <= last_key.as_slice() {
+                    bail!(
+                        "MinimalAcyclicBuilder : keys must be inserted in increasing order, {:?} is after {:?}",
+                        key,
+                        last_key
+                    );
+                }
+                key.iter()
+                    .zip(last_key.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            }
+            None => 0,
+        };
+
+        self.freeze_from(prefix_len + 1)?;
+
+        // Pushes the weights towards the initial state along the common prefix.
+        let mut weight = weight;
+        for depth in 0..prefix_len {
+            let tr = self.unfinished[depth].trs.last_mut().unwrap();
+            let common = tr.weight.plus(&weight)?;
+            let rest = tr.weight.divide(&common, DivideType::DivideLeft)?;
+            weight = weight.divide(&common, DivideType::DivideLeft)?;
+            tr.weight = common;
+            self.unfinished[depth + 1].premultiply(&rest)?;
+        }
+
+        if prefix_len == key.len() {
+            // Only the empty key can end on an unfinished state.
+            self.unfinished[prefix_len].final_weight = Some(weight);
+        } else {
+            for (idx, label) in key.iter().enumerate().skip(prefix_len) {
+                let tr_weight = if idx == prefix_len {
+                    weight.clone()
+                } else {
+                    W::one()
+                };
+                self.unfinished[idx]
+                    .trs
+                    .push(Tr::new(*label, *label, tr_weight, 0));
+                self.unfinished.push(UnfinishedState::new());
+            }
+            self.unfinished.last_mut().unwrap().final_weight = Some(W::one());
+        }
+
+        self.last_key = Some(key.to_vec());
+        Ok(())
+    }
+
+    /// Freezes all the states and returns the acceptor, e.g a `VectorFst` or a `ConstFst`.
+    pub fn finish<F: From<VectorFst<W>>>(mut self) -> Result<F> {
+        self.freeze_from(1)?;
+        let root = self.unfinished.pop().unwrap();
+        let start = self.freeze(root)?;
+        self.fst.set_start(start)?;
+        Ok(self.fst.into())
+    }
+
+    /// Freezes the unfinished states with a depth greater or equal to `depth`.
+    fn freeze_from(&mut self, depth: usize) -> Result<()> {
+        while self.unfinished.len() > depth {
+            let state = self.unfinished.pop().unwrap();
+            let nextstate = self.freeze(state)?;
+            self.unfinished
+                .last_mut()
+                .unwrap()
+                .trs
+                .last_mut()
+                .unwrap()
+                .nextstate = nextstate;
+        }
+        Ok(())
+    }
+
+    fn freeze(&mut self, state: UnfinishedState<W>) -> Result<StateId> {
+        let key = (state.final_weight, state.trs);
+        if let Some(s) = self.registry.get(&key) {
+            return Ok(*s);
+        }
+        let s = self.fst.add_state();
+        if let Some(final_weight) = &key.0 {
+            self.fst.set_final(s, final_weight.clone())?;
+        }
+        for tr in &key.1 {
+            self.fst.add_tr(s, tr.clone())?;
+        }
+        self.registry.insert(key, s);
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fst_impls::ConstFst;
+    use crate::fst_properties::FstProperties;
+    use crate::fst_traits::{CoreFst, ExpandedFst, PathsIterator};
+    use crate::semirings::{LogWeight, Semiring, TropicalWeight};
+
+    fn paths<W: WeaklyDivisibleSemiring>(fst: &VectorFst<W>) -> Vec<(Vec<Label>, W)> {
+        let mut paths: Vec<_> = fst.paths_iter().map(|p| (p.ilabels, p.weight)).collect();
+        paths.sort_by(|a, b| a.0.cmp(&b.0));
+        paths
+    }
+
+    #[test]
+    fn test_minimal_acyclic_builder_unweighted() -> Result<()> {
+        let keys = vec![
+            vec![1, 2, 3],
+            vec![1, 2, 3, 4],
+            vec![1, 5, 3],
+            vec![1, 5, 3, 4],
+        ];
+        let mut builder = MinimalAcyclicBuilder::new();
+        for key in &keys {
+            builder.insert(key, TropicalWeight::one())?;
+        }
+        let fst: VectorFst<TropicalWeight> = builder.finish()?;
+
+        assert_eq!(fst.num_states(), 5);
+        assert!(fst
+            .properties()?
+            .contains(FstProperties::I_DETERMINISTIC | FstProperties::ACYCLIC));
+        let labels: Vec<_> = paths(&fst).into_iter().map(|p| p.0).collect();
+        assert_eq!(labels, keys);
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_acyclic_builder_weights_pushed() -> Result<()> {
+        let mut builder = MinimalAcyclicBuilder::new();
+        builder.insert(&[], TropicalWeight::new(4.0))?;
+        builder.insert(&[1, 2], TropicalWeight::new(3.0))?;
+        builder.insert(&[1, 3], TropicalWeight::new(1.0))?;
+        builder.insert(&[2, 2], TropicalWeight::new(2.0))?;
+        builder.insert(&[2, 3], TropicalWeight::new(0.0))?;
+        let fst: VectorFst<TropicalWeight> = builder.finish()?;
+
+        // The states reached by 1 and 2 only differ by a weight of 1.0 pushed on their trs.
+        assert_eq!(fst.num_states(), 3);
+        assert_eq!(
+            paths(&fst),
+            vec![
+                (vec![], TropicalWeight::new(4.0)),
+                (vec![1, 2], TropicalWeight::new(3.0)),
+                (vec![1, 3], TropicalWeight::new(1.0)),
+                (vec![2, 2], TropicalWeight::new(2.0)),
+                (vec![2, 3], TropicalWeight::new(0.0)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_acyclic_builder_log_weights() -> Result<()> {
+        let entries = vec![
+            (vec![1], LogWeight::new(1.0)),
+            (vec![1, 1], LogWeight::new(2.0)),
+            (vec![1, 2], LogWeight::new(3.0)),
+            (vec![3], LogWeight::new(0.5)),
+        ];
+        let mut builder = MinimalAcyclicBuilder::new();
+        for (key, weight) in &entries {
+            builder.insert(key, *weight)?;
+        }
+        let fst: ConstFst<LogWeight> = builder.finish()?;
+        let fst: VectorFst<LogWeight> = crate::algorithms::fst_convert_from_ref(&fst);
+
+        for ((labels, weight), (expected_labels, expected_weight)) in
+            paths(&fst).into_iter().zip(entries)
+        {
+            assert_eq!(labels, expected_labels);
+            assert!((weight.value() - expected_weight.value()).abs() < 1e-5);
+        }
+        assert_eq!(fst.start(), Some(fst.num_states() - 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_acyclic_builder_errors() -> Result<()> {
+        let mut builder = MinimalAcyclicBuilder::new();
+        builder.insert(&[1, 2], TropicalWeight::one())?;
+        assert!(builder.insert(&[1, 2], TropicalWeight::one()).is_err());
+        assert!(builder.insert(&[1], TropicalWeight::one()).is_err());
+        assert!(builder.insert(&[2, 0], TropicalWeight::one()).is_err());
+        assert!(builder.insert(&[3], TropicalWeight::zero()).is_err());
+        Ok(())
+    }
+}
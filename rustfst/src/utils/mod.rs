@@ -1,5 +1,21 @@
+mod cross;
+mod edit_transducer;
 mod fst_to_labels;
 mod labels_to_fst;
+mod levenshtein_automaton;
+mod minimal_acyclic_builder;
+mod path_to_string;
+mod string_map;
+mod string_to_fst;
+mod token_type;
 
+pub use self::cross::cross;
+pub use self::edit_transducer::{EditLattice, EditTransducer};
 pub use self::fst_to_labels::decode_linear_fst;
 pub use self::labels_to_fst::{acceptor, transducer};
+pub use self::levenshtein_automaton::levenshtein_automaton;
+pub use self::minimal_acyclic_builder::MinimalAcyclicBuilder;
+pub use self::path_to_string::print_string;
+pub use self::string_map::string_map;
+pub use self::string_to_fst::compile_string;
+pub use self::token_type::TokenType;
@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use crate::fst_path::FstPath;
+use crate::semirings::Semiring;
+use crate::utils::TokenType;
+
+/// Turns the output labels of a path back into a string, according to the `TokenType`.
+/// Epsilon labels are skipped and, with a `SymbolTable`, the symbols are separated by a space.
+/// This is the inverse of `compile_string`.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::semirings::{ProbabilityWeight, Semiring};
+/// # use rustfst::utils::{compile_string, print_string, TokenType};
+/// # fn main() -> Result<()> {
+/// let fst: VectorFst<ProbabilityWeight> =
+///     compile_string("hé", &TokenType::Byte, ProbabilityWeight::one())?;
+/// let strings = fst
+///     .paths_iter()
+///     .map(|p| print_string(&p, &TokenType::Byte))
+///     .collect::<Result<Vec<_>>>()?;
+/// assert_eq!(strings, vec!["hé".to_string()]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn print_string<W: Semiring>(fst_path: &FstPath<W>, token_type: &TokenType) -> Result<String> {
+    token_type.detokenize(&fst_path.olabels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{Fst, PathsIterator};
+    use crate::semirings::TropicalWeight;
+    use crate::utils::{compile_string, transducer};
+    use crate::SymbolTable;
+
+    fn roundtrip(s: &str, token_type: &TokenType) -> Result<String> {
+        let fst: VectorFst<TropicalWeight> =
+            compile_string(s, token_type, TropicalWeight::new(2.0))?;
+        let path = fst.paths_iter().next().unwrap();
+        assert_eq!(path.weight, TropicalWeight::new(2.0));
+        print_string(&path, token_type)
+    }
+
+    #[test]
+    fn test_string_roundtrip() -> Result<()> {
+        for s in &["", "abc", "naïve café", "日本語"] {
+            assert_eq!(roundtrip(s, &TokenType::Byte)?, *s);
+            assert_eq!(roundtrip(s, &TokenType::Utf8)?, *s);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_symbol_table() -> Result<()> {
+        let mut symt = SymbolTable::new();
+        symt.add_symbols(vec!["the", "cat"]);
+        let token_type = TokenType::SymbolTable(Arc::new(symt));
+        assert_eq!(roundtrip(" the cat\tthe ", &token_type)?, "the cat the");
+        assert!(roundtrip("the dog", &token_type).is_err());
+
+        let fst: VectorFst<TropicalWeight> =
+            compile_string("cat", &token_type, TropicalWeight::one())?;
+        assert!(fst.input_symbols().is_some());
+        assert!(fst.output_symbols().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_string_output_labels() -> Result<()> {
+        let fst: VectorFst<TropicalWeight> =
+            transducer(&[97, 0, 98], &[120, 121, 0], TropicalWeight::one());
+        let path = fst.paths_iter().next().unwrap();
+        assert_eq!(print_string(&path, &TokenType::Utf8)?, "xy");
+        assert!(print_string(
+            &FstPath::new(vec![], vec![300], TropicalWeight::one()),
+            &TokenType::Byte
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::algorithms::minimize;
+use crate::fst_traits::{AllocableFst, ExpandedFst, MutableFst};
+use crate::semirings::{WeaklyDivisibleSemiring, WeightQuantize};
+use crate::utils::TokenType;
+use crate::{Label, StateId, Tr, EPS_LABEL};
+
+/// Builds a transducer mapping each input string to its output strings from a list of
+/// `(input, output, weight)` triples, e.g a lexicon.
+///
+/// The transducer is built as a prefix tree : the input labels are read first (with epsilon
+/// outputs) sharing the common prefixes of the inputs, then the output labels are written (with
+/// epsilon inputs) sharing the common prefixes of the outputs of a given input. The weight is put
+/// on the final state and the weights of duplicated pairs are summed. It is built in a single
+/// pass, contrary to a `union` of linear transducers. If `minimize` is true, the result is then
+/// minimized.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::fst_traits::{ExpandedFst, PathsIterator};
+/// # use rustfst::semirings::{Semiring, TropicalWeight};
+/// # use rustfst::utils::{print_string, string_map, TokenType};
+/// # fn main() -> Result<()> {
+/// let lexicon = vec![
+///     ("cat", "kæt", TropicalWeight::one()),
+///     ("car", "kɑːr", TropicalWeight::one()),
+/// ];
+/// let fst: VectorFst<TropicalWeight> =
+///     string_map(lexicon, &TokenType::Utf8, &TokenType::Utf8, false)?;
+///
+/// // 1 root + 2 + 2 leaves for the inputs, 3 + 4 states for the outputs.
+/// assert_eq!(fst.num_states(), 12);
+/// let mut outputs = fst
+///     .paths_iter()
+///     .map(|p| print_string(&p, &TokenType::Utf8))
+///     .collect::<Result<Vec<_>>>()?;
+/// outputs.sort();
+/// assert_eq!(outputs, vec!["kæt", "kɑːr"]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn string_map<W, F, I, S1, S2>(
+    entries: I,
+    input_token_type: &TokenType,
+    output_token_type: &TokenType,
+    minimize_fst: bool,
+) -> Result<F>
+where
+    W: WeaklyDivisibleSemiring + WeightQuantize,
+    F: MutableFst<W> + ExpandedFst<W> + AllocableFst<W>,
+    I: IntoIterator<Item = (S1, S2, W)>,
+    S1: AsRef<str>,
+    S2: AsRef<str>,
+{
+    let mut fst = F::new();
+    let root = fst.add_state();
+    fst.set_start(root)?;
+    // Children of each state, indexed by (state, ilabel, olabel).
+    let mut children: HashMap<(StateId, Label, Label), StateId> = HashMap::new();
+
+    for (input, output, weight) in entries {
+        let ilabels = input_token_type.tokenize(input.as_ref())?;
+        let olabels = output_token_type.tokenize(output.as_ref())?;
+        let trs = ilabels
+            .into_iter()
+            .map(|l| (l, EPS_LABEL))
+            .chain(olabels.into_iter().map(|l| (EPS_LABEL, l)));
+
+        let mut state = root;
+        for (ilabel, olabel) in trs {
+            state = match children.get(&(state, ilabel, olabel)) {
+                Some(next_state) => *next_state,
+                None => {
+                    let next_state = fst.add_state();
+                    fst.add_tr(state, Tr::new(ilabel, olabel, W::one(), next_state))?;
+                    children.insert((state, ilabel, olabel), next_state);
+                    next_state
+                }
+            };
+        }
+        let final_weight = match fst.final_weight(state)? {
+            Some(w) => w.plus(weight)?,
+            None => weight,
+        };
+        fst.set_final(state, final_weight)?;
+    }
+
+    if minimize_fst {
+        minimize(&mut fst, true)?;
+    }
+
+    if let Some(symt) = input_token_type.symbol_table() {
+        fst.set_input_symbols(symt);
+    }
+    if let Some(symt) = output_token_type.symbol_table() {
+        fst.set_output_symbols(symt);
+    }
+    Ok(fst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::fst_impls::VectorFst;
+    use crate::fst_traits::{Fst, PathsIterator};
+    use crate::semirings::{Semiring, TropicalWeight};
+    use crate::SymbolTable;
+
+    fn sorted_paths(
+        fst: &VectorFst<TropicalWeight>,
+    ) -> Vec<(Vec<Label>, Vec<Label>, TropicalWeight)> {
+        let mut paths: Vec<_> = fst
+            .paths_iter()
+            .map(|p| (p.ilabels, p.olabels, p.weight))
+            .collect();
+        paths.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        paths
+    }
+
+    #[test]
+    fn test_string_map_trie() -> Result<()> {
+        let entries = vec![
+            ("ab", "x", TropicalWeight::new(1.0)),
+            ("ac", "x", TropicalWeight::new(2.0)),
+            ("ab", "yz", TropicalWeight::new(3.0)),
+            ("ab", "x", TropicalWeight::new(0.5)),
+        ];
+        let fst: VectorFst<TropicalWeight> =
+            string_map(entries, &TokenType::Byte, &TokenType::Byte, false)?;
+        // root, a, ab, ac, ab:x, ab:y, ab:yz, ac:x
+        assert_eq!(fst.num_states(), 8);
+        assert_eq!(
+            sorted_paths(&fst),
+            vec![
+                (vec![97, 98], vec![120], TropicalWeight::new(0.5)),
+                (vec![97, 98], vec![121, 122], TropicalWeight::new(3.0)),
+                (vec![97, 99], vec![120], TropicalWeight::new(2.0)),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_map_minimize() -> Result<()> {
+        let entries = vec![
+            ("ab", "x", TropicalWeight::new(1.0)),
+            ("cb", "x", TropicalWeight::new(1.0)),
+        ];
+        let fst: VectorFst<TropicalWeight> =
+            string_map(entries.clone(), &TokenType::Byte, &TokenType::Byte, false)?;
+        let fst_min: VectorFst<TropicalWeight> =
+            string_map(entries, &TokenType::Byte, &TokenType::Byte, true)?;
+        assert!(fst_min.num_states() < fst.num_states());
+        assert_eq!(sorted_paths(&fst_min), sorted_paths(&fst));
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_map_symbol_tables() -> Result<()> {
+        let mut isymt = SymbolTable::new();
+        isymt.add_symbols(vec!["one", "two"]);
+        let input_token_type = TokenType::SymbolTable(Arc::new(isymt));
+        let entries = vec![("one two", "12", TropicalWeight::one())];
+        let fst: VectorFst<TropicalWeight> =
+            string_map(entries, &input_token_type, &TokenType::Utf8, false)?;
+        assert_eq!(
+            sorted_paths(&fst),
+            vec![(vec![1, 2], vec![49, 50], TropicalWeight::one())]
+        );
+        assert!(fst.input_symbols().is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::fst_traits::MutableFst;
+use crate::semirings::Semiring;
+use crate::utils::{acceptor, TokenType};
+
+/// Turns a string into a linear acceptor, tokenizing it according to the `TokenType`.
+/// With a `SymbolTable` the string is split on whitespaces and the table is attached to the FST.
+///
+/// # Example
+///
+/// ```
+/// # use anyhow::Result;
+/// # use std::sync::Arc;
+/// # use rustfst::fst_traits::PathsIterator;
+/// # use rustfst::fst_impls::VectorFst;
+/// # use rustfst::semirings::{ProbabilityWeight, Semiring};
+/// # use rustfst::utils::{compile_string, TokenType};
+/// # use rustfst::SymbolTable;
+/// # fn main() -> Result<()> {
+/// let fst: VectorFst<ProbabilityWeight> =
+///     compile_string("hé", &TokenType::Byte, ProbabilityWeight::one())?;
+/// assert_eq!(fst.paths_iter().next().unwrap().ilabels, vec![104, 195, 169]);
+///
+/// let fst: VectorFst<ProbabilityWeight> =
+///     compile_string("hé", &TokenType::Utf8, ProbabilityWeight::one())?;
+/// assert_eq!(fst.paths_iter().next().unwrap().ilabels, vec![104, 233]);
+///
+/// let mut symt = SymbolTable::new();
+/// symt.add_symbols(vec!["hello", "world"]);
+/// let token_type = TokenType::SymbolTable(Arc::new(symt));
+/// let fst: VectorFst<ProbabilityWeight> =
+///     compile_string("hello  world", &token_type, ProbabilityWeight::one())?;
+/// assert_eq!(fst.paths_iter().next().unwrap().ilabels, vec![1, 2]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn compile_string<W: Semiring, F: MutableFst<W>>(
+    s: &str,
+    token_type: &TokenType,
+    weight: W,
+) -> Result<F> {
+    let labels = token_type.tokenize(s)?;
+    let mut fst: F = acceptor(&labels, weight);
+    if let Some(symt) = token_type.symbol_table() {
+        fst.set_input_symbols(Arc::clone(&symt));
+        fst.set_output_symbols(symt);
+    }
+    Ok(fst)
+}
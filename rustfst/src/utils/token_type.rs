@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use anyhow::{bail, format_err, Result};
+
+use crate::{Label, SymbolTable, EPS_LABEL};
+
+/// Defines how a string is split into labels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenType {
+    /// Each byte of the UTF-8 encoding of the string is a label.
+    Byte,
+    /// Each unicode code point of the string is a label.
+    Utf8,
+    /// Labels are looked up in a `SymbolTable`.
+    SymbolTable(Arc<SymbolTable>),
+}
+
+impl TokenType {
+    /// Labels representing a single character.
+    pub(crate) fn char_to_labels(&self, c: char) -> Result<Vec<Label>> {
+        match self {
+            TokenType::Byte => {
+                let mut buf = [0u8; 4];
+                Ok(c.encode_utf8(&mut buf)
+                    .bytes()
+                    .map(|b| b as Label)
+                    .collect())
+            }
+            TokenType::Utf8 => Ok(vec![c as Label]),
+            TokenType::SymbolTable(symt) => {
+                let mut buf = [0u8; 4];
+                Ok(vec![self.symbol_to_label(symt, c.encode_utf8(&mut buf))?])
+            }
+        }
+    }
+
+    /// Label of a symbol made of several characters. Only available with a `SymbolTable`.
+    pub(crate) fn multichar_symbol_to_label(&self, symbol: &str) -> Result<Label> {
+        match self {
+            TokenType::SymbolTable(symt) => self.symbol_to_label(symt, symbol),
+            _ => Err(format_err!(
+                "Multi-character symbol `{}` requires a SymbolTable token type",
+                symbol
+            )),
+        }
+    }
+
+    fn symbol_to_label(&self, symt: &SymbolTable, symbol: &str) -> Result<Label> {
+        symt.get_label(symbol)
+            .ok_or_else(|| format_err!("Symbol `{}` is missing from the SymbolTable", symbol))
+    }
+
+    /// Splits a string into labels. With a `SymbolTable`, the string is split on whitespaces.
+    pub(crate) fn tokenize(&self, s: &str) -> Result<Vec<Label>> {
+        match self {
+            TokenType::Byte => Ok(s.bytes().map(|b| b as Label).collect()),
+            TokenType::Utf8 => Ok(s.chars().map(|c| c as Label).collect()),
+            TokenType::SymbolTable(symt) => s
+                .split_whitespace()
+                .map(|symbol| self.symbol_to_label(symt, symbol))
+                .collect(),
+        }
+    }
+
+    /// Inverse of `tokenize`. Epsilon labels are skipped.
+    pub(crate) fn detokenize(&self, labels: &[Label]) -> Result<String> {
+        let labels = labels.iter().cloned().filter(|l| *l != EPS_LABEL);
+        match self {
+            TokenType::Byte => {
+                let bytes = labels
+                    .map(|l| {
+                        if l > u8::MAX as Label {
+                            bail!("Label {} is not a byte", l)
+                        }
+                        Ok(l as u8)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(String::from_utf8(bytes)?)
+            }
+            TokenType::Utf8 => labels
+                .map(|l| {
+                    std::char::from_u32(l as u32)
+                        .ok_or_else(|| format_err!("Label {} is not a unicode code point", l))
+                })
+                .collect(),
+            TokenType::SymbolTable(symt) => {
+                let symbols = labels
+                    .map(|l| {
+                        symt.get_symbol(l).ok_or_else(|| {
+                            format_err!("Label {} is missing from the SymbolTable", l)
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(symbols.join(" "))
+            }
+        }
+    }
+
+    /// Default alphabet : all the bytes for `Byte`, the ASCII code points for `Utf8`
+    /// and all the non-epsilon labels of the `SymbolTable`.
+    pub(crate) fn sigma(&self) -> Vec<Label> {
+        match self {
+            TokenType::Byte => (1..=255).collect(),
+            TokenType::Utf8 => (1..=127).collect(),
+            TokenType::SymbolTable(symt) => {
+                let mut labels: Vec<_> =
+                    symt.labels().cloned().filter(|l| *l != EPS_LABEL).collect();
+                labels.sort();
+                labels
+            }
+        }
+    }
+
+    /// Symbol table to attach to the FSTs built with this token type, if any.
+    pub(crate) fn symbol_table(&self) -> Option<Arc<SymbolTable>> {
+        match self {
+            TokenType::SymbolTable(symt) => Some(Arc::clone(symt)),
+            _ => None,
+        }
+    }
+}